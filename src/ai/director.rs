@@ -0,0 +1,234 @@
+use crate::maze::{Coord, Maze};
+use bevy::log;
+use std::time::Duration;
+
+/// The pacing cycle of the director, loosely modeled on the one in Left 4 Dead.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DirectorPhase {
+    /// enemies trickle in until the player is stressed enough
+    BuildUp,
+    /// no new enemies, the player deals with what's already there
+    Peak,
+    /// quiet time until the player has calmed down
+    Relax,
+}
+
+/// What happened to the player since the last update.
+#[derive(Debug, Default, Clone)]
+pub struct StressSignals {
+    pub hits_taken: u32,
+    pub kills: u32,
+    /// enemies within `DirectorConfig::nearby_radius` cells of the player
+    pub enemies_nearby: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct DirectorConfig {
+    /// stress at which the build up turns into a peak
+    pub peak_stress: f32,
+    /// stress the player has to get below before the next build up
+    pub relax_stress: f32,
+    pub peak_duration: Duration,
+    /// minimum time spent relaxing
+    pub relax_duration: Duration,
+    /// time between spawns during the build up
+    pub spawn_interval: Duration,
+    /// spawns twice as fast when the player hasn't killed anything for this long
+    pub idle_duration: Duration,
+    pub stress_per_hit: f32,
+    pub stress_per_kill: f32,
+    /// stress per second for each nearby enemy
+    pub stress_per_nearby_enemy: f32,
+    /// stress lost per second while there are no enemies nearby
+    pub stress_decay: f32,
+    pub nearby_radius: usize,
+    /// enemies never spawn closer to the player than this (in cells)
+    pub min_spawn_distance: usize,
+}
+
+impl Default for DirectorConfig {
+    fn default() -> Self {
+        Self {
+            peak_stress: 0.8,
+            relax_stress: 0.2,
+            peak_duration: Duration::from_secs(5),
+            relax_duration: Duration::from_secs(10),
+            spawn_interval: Duration::from_secs(3),
+            idle_duration: Duration::from_secs(8),
+            stress_per_hit: 0.05,
+            stress_per_kill: 0.1,
+            stress_per_nearby_enemy: 0.05,
+            stress_decay: 0.1,
+            nearby_radius: 4,
+            min_spawn_distance: 5,
+        }
+    }
+}
+
+/// Decides when and where dormant enemy spawns get activated, based on how stressed the player seems.
+#[derive(Debug)]
+pub struct Director {
+    pub config: DirectorConfig,
+    pub phase: DirectorPhase,
+    /// estimated player stress, in 0..=1
+    pub stress: f32,
+    pub time_since_last_kill: Duration,
+    time_in_phase: Duration,
+    time_since_last_spawn: Duration,
+}
+
+impl Default for Director {
+    fn default() -> Self {
+        Self::new(DirectorConfig::default())
+    }
+}
+
+impl Director {
+    pub fn new(config: DirectorConfig) -> Self {
+        Self {
+            config,
+            phase: DirectorPhase::BuildUp,
+            stress: 0.,
+            time_since_last_kill: Duration::ZERO,
+            time_in_phase: Duration::ZERO,
+            time_since_last_spawn: Duration::ZERO,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config.clone());
+    }
+
+    /// updates the stress estimate and the phase, returns true if an enemy should be spawned now
+    pub fn update(&mut self, dt: Duration, signals: &StressSignals) -> bool {
+        let dt_secs = dt.as_secs_f32();
+        let config = &self.config;
+
+        self.stress += signals.hits_taken as f32 * config.stress_per_hit;
+        self.stress += signals.kills as f32 * config.stress_per_kill;
+        if signals.enemies_nearby > 0 {
+            self.stress += signals.enemies_nearby as f32 * config.stress_per_nearby_enemy * dt_secs;
+        } else {
+            self.stress -= config.stress_decay * dt_secs;
+        }
+        self.stress = self.stress.clamp(0., 1.);
+
+        if signals.kills > 0 {
+            self.time_since_last_kill = Duration::ZERO;
+        } else {
+            self.time_since_last_kill += dt;
+        }
+        self.time_in_phase += dt;
+        self.time_since_last_spawn += dt;
+
+        let next_phase = match self.phase {
+            DirectorPhase::BuildUp if self.stress >= config.peak_stress => {
+                Some(DirectorPhase::Peak)
+            }
+            DirectorPhase::Peak if self.time_in_phase >= config.peak_duration => {
+                Some(DirectorPhase::Relax)
+            }
+            DirectorPhase::Relax
+                if self.time_in_phase >= config.relax_duration
+                    && self.stress <= config.relax_stress =>
+            {
+                Some(DirectorPhase::BuildUp)
+            }
+            _ => None,
+        };
+
+        if let Some(next_phase) = next_phase {
+            log::info!(
+                "director: {:?} -> {:?} (stress {:.2})",
+                self.phase,
+                next_phase,
+                self.stress
+            );
+            self.phase = next_phase;
+            self.time_in_phase = Duration::ZERO;
+            self.time_since_last_spawn = Duration::ZERO;
+        }
+
+        if self.phase != DirectorPhase::BuildUp {
+            return false;
+        }
+
+        let spawn_interval = if self.time_since_last_kill >= self.config.idle_duration {
+            self.config.spawn_interval / 2
+        } else {
+            self.config.spawn_interval
+        };
+
+        if self.time_since_last_spawn >= spawn_interval {
+            self.time_since_last_spawn = Duration::ZERO;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// the closest enemy spawn the player can't see that is far enough away and not occupied
+    pub fn choose_spawn(&self, maze: &Maze, player: Coord, occupied: &[Coord]) -> Option<Coord> {
+        let distance = |(x, y): Coord| x.abs_diff(player.0) + y.abs_diff(player.1);
+
+        maze.enemy_spawn_coords()
+            .into_iter()
+            .filter(|coord| !occupied.contains(coord))
+            .filter(|&coord| distance(coord) >= self.config.min_spawn_distance)
+            .filter(|&coord| !maze.line_of_sight(player, coord))
+            .min_by_key(|&coord| distance(coord))
+    }
+}
+
+#[test]
+fn test_director_cycles_through_phases() {
+    let mut director = Director::default();
+    let second = Duration::from_secs(1);
+    let calm = StressSignals::default();
+
+    // enemies are spawned while building up
+    let spawns = (0..10).filter(|_| director.update(second, &calm)).count();
+    assert_eq!(director.phase, DirectorPhase::BuildUp);
+    assert!(spawns > 0);
+
+    let fight = StressSignals {
+        hits_taken: 5,
+        kills: 2,
+        enemies_nearby: 3,
+    };
+    while director.phase == DirectorPhase::BuildUp {
+        director.update(second, &fight);
+    }
+    assert_eq!(director.phase, DirectorPhase::Peak);
+
+    // nothing spawns during the peak or while relaxing
+    let mut spawned = false;
+    while director.phase != DirectorPhase::BuildUp {
+        spawned |= director.update(second, &calm);
+    }
+    assert!(!spawned);
+    assert!(director.stress <= director.config.relax_stress);
+}
+
+#[test]
+fn test_director_spawns_out_of_sight() {
+    use crate::util::Array2D;
+
+    let maze = Maze {
+        grid: Array2D::from(
+            "\
+            #########\n\
+            #P.....E#\n\
+            #.#######\n\
+            #......E#\n\
+            #########\n"
+                .to_string(),
+        ),
+    };
+    let director = Director::default();
+    let player = maze.player_spawn_coord().unwrap();
+
+    // the spawn on the same row is closer but visible
+    assert_eq!(director.choose_spawn(&maze, player, &[]), Some((7, 3)));
+    assert_eq!(director.choose_spawn(&maze, player, &[(7, 3)]), None);
+}
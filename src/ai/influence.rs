@@ -0,0 +1,294 @@
+use crate::maze::{Coord, Maze, Symbol, SymbolConsts};
+use crate::util::pathfinding::walkable_neighbours;
+use crate::util::{Array2D, Direction};
+use std::collections::VecDeque;
+
+/// How a layer spreads and fades between ticks.
+#[derive(Debug, Clone, Copy)]
+pub struct LayerConfig {
+    /// fraction of the influence kept every tick
+    pub decay: f32,
+    /// fraction of a cell's influence that spreads to its neighbours every tick
+    pub propagation: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct InfluenceConfig {
+    pub player_threat: LayerConfig,
+    pub enemy_presence: LayerConfig,
+    pub recent_danger: LayerConfig,
+    /// how many of the player's last cells are remembered to guess where it's headed
+    pub trail_length: usize,
+}
+
+impl Default for InfluenceConfig {
+    fn default() -> Self {
+        Self {
+            player_threat: LayerConfig {
+                decay: 0.9,
+                propagation: 0.7,
+            },
+            enemy_presence: LayerConfig {
+                decay: 0.8,
+                propagation: 0.5,
+            },
+            // danger lingers for a while after something happened
+            recent_danger: LayerConfig {
+                decay: 0.98,
+                propagation: 0.3,
+            },
+            trail_length: 8,
+        }
+    }
+}
+
+/// What happened on the map during the last tick.
+#[derive(Debug, Default, Clone)]
+pub struct InfluenceSources {
+    pub player: Option<Coord>,
+    pub enemies: Vec<Coord>,
+    pub bullets: Vec<Coord>,
+    pub kills: Vec<Coord>,
+}
+
+/// Layers of influence over the maze, each cell in 0..=1. AI decisions query these instead of looking at
+///  individual entities.
+#[derive(Debug)]
+pub struct InfluenceMaps {
+    pub config: InfluenceConfig,
+    /// where the player can hurt you, highest at the player and along its lines of fire
+    pub player_threat: Array2D<f32>,
+    pub enemy_presence: Array2D<f32>,
+    /// where bullets flew and enemies died recently
+    pub recent_danger: Array2D<f32>,
+    // the last cells the player was in, most recent last
+    player_trail: VecDeque<Coord>,
+}
+
+impl InfluenceMaps {
+    pub fn new(width: usize, height: usize, config: InfluenceConfig) -> Self {
+        Self {
+            config,
+            player_threat: Array2D::new(width, height, 0.),
+            enemy_presence: Array2D::new(width, height, 0.),
+            recent_danger: Array2D::new(width, height, 0.),
+            player_trail: VecDeque::new(),
+        }
+    }
+
+    /// decays and propagates every layer, then stamps the new sources onto them
+    pub fn update(&mut self, maze: &Maze, sources: &InfluenceSources) {
+        propagate(&mut self.player_threat, maze, self.config.player_threat);
+        propagate(&mut self.enemy_presence, maze, self.config.enemy_presence);
+        propagate(&mut self.recent_danger, maze, self.config.recent_danger);
+
+        if let Some(player) = sources.player {
+            stamp(&mut self.player_threat, player, 1.);
+            // bullets fly straight, everything in a straight line from the player is in danger
+            for dir in Direction::ALL {
+                let mut cell = player;
+                while let Some(next) = dir.step(cell, (maze.width, maze.height)) {
+                    if !maze.is_walkable(next) {
+                        break;
+                    }
+                    stamp(&mut self.player_threat, next, 0.8);
+                    cell = next;
+                }
+            }
+
+            if self.player_trail.back() != Some(&player) {
+                self.player_trail.push_back(player);
+                if self.player_trail.len() > self.config.trail_length {
+                    self.player_trail.pop_front();
+                }
+            }
+        }
+
+        for &enemy in &sources.enemies {
+            stamp(&mut self.enemy_presence, enemy, 1.);
+        }
+        for &cell in sources.bullets.iter().chain(sources.kills.iter()) {
+            stamp(&mut self.recent_danger, cell, 1.);
+        }
+    }
+
+    /// how dangerous a cell is for an enemy to stand in
+    pub fn danger_at(&self, coord: Coord) -> f32 {
+        self.player_threat.get(coord) + self.recent_danger.get(coord)
+    }
+
+    /// the safest cell within `max_steps` steps of `from`, preferring closer cells on ties
+    pub fn cover_cell(&self, maze: &Maze, from: Coord, max_steps: usize) -> Option<Coord> {
+        cells_within_steps(maze, from, max_steps)
+            .into_iter()
+            .min_by(|&(a, a_steps), &(b, b_steps)| {
+                self.danger_at(a)
+                    .total_cmp(&self.danger_at(b))
+                    .then(a_steps.cmp(&b_steps))
+            })
+            .map(|(cell, _)| cell)
+    }
+
+    /// Guesses where the player will be in `steps` cells, by following the direction it has been moving in.
+    /// At a wall it turns into the first open side corridor, it never turns back.
+    pub fn predicted_player_cell(&self, maze: &Maze, steps: usize) -> Option<Coord> {
+        let current = *self.player_trail.back()?;
+        let previous = match self.player_trail.iter().rev().nth(1) {
+            Some(&previous) => previous,
+            None => return Some(current),
+        };
+
+        let mut heading = Direction::ALL
+            .into_iter()
+            .find(|dir| dir.step(previous, (maze.width, maze.height)) == Some(current))?;
+
+        let mut cell = current;
+        for _ in 0..steps {
+            let open = |dir: Direction| {
+                dir.step(cell, (maze.width, maze.height))
+                    .filter(|&next| maze.is_walkable(next))
+            };
+
+            match open(heading) {
+                Some(next) => cell = next,
+                None => {
+                    let back = heading.opposite();
+                    let turn = Direction::ALL
+                        .into_iter()
+                        .filter(|&dir| dir != heading && dir != back)
+                        .find_map(|dir| open(dir).map(|next| (dir, next)));
+
+                    match turn {
+                        Some((dir, next)) => {
+                            heading = dir;
+                            cell = next;
+                        }
+                        None => break, // dead end
+                    }
+                }
+            }
+        }
+        Some(cell)
+    }
+
+    /// A cell near where the player is headed that the player can't shoot at and where no other enemy is
+    ///  waiting already.
+    pub fn ambush_cell(&self, maze: &Maze, lookahead: usize, radius: usize) -> Option<Coord> {
+        let predicted = self.predicted_player_cell(maze, lookahead)?;
+
+        cells_within_steps(maze, predicted, radius)
+            .into_iter()
+            // don't wait on the player's own path
+            .filter(|&(_, steps)| steps > 0)
+            .min_by(|&(a, _), &(b, _)| {
+                let score = |cell| self.danger_at(cell) + self.enemy_presence.get(cell);
+                score(a).total_cmp(&score(b))
+            })
+            .map(|(cell, _)| cell)
+    }
+}
+
+fn stamp(layer: &mut Array2D<f32>, coord: Coord, value: f32) {
+    if *layer.get(coord) < value {
+        layer.set(coord, value);
+    }
+}
+
+/// every walkable cell takes the highest of its own and its neighbours' spread influence, then decays
+fn propagate(layer: &mut Array2D<f32>, maze: &Maze, config: LayerConfig) {
+    let mut next = Array2D::new(layer.width, layer.height, 0.);
+
+    for (coord, &value) in layer.iter_rows_first_enumerated() {
+        if !maze.is_walkable(coord) {
+            continue;
+        }
+
+        let spread = walkable_neighbours(maze, coord)
+            .map(|neighbour| layer.get(neighbour) * config.propagation)
+            .fold(value, f32::max);
+
+        next.set(coord, spread * config.decay);
+    }
+
+    *layer = next;
+}
+
+/// walkable cells reachable in at most `max_steps` steps, with the number of steps to each
+fn cells_within_steps(maze: &Maze, from: Coord, max_steps: usize) -> Vec<(Coord, usize)> {
+    let steps = maze.bfs_distances([from], |&symbol| symbol != Symbol::BLOCKED);
+    steps
+        .iter_rows_first_enumerated()
+        .filter_map(|(cell, &steps)| Some((cell, steps? as usize)))
+        .filter(|&(_, steps)| steps <= max_steps)
+        .collect()
+}
+
+#[cfg(test)]
+fn test_maze() -> Maze {
+    Maze {
+        grid: Array2D::from(
+            "\
+            #########\n\
+            #.......#\n\
+            #.#####.#\n\
+            #.#.....#\n\
+            #########\n"
+                .to_string(),
+        ),
+    }
+}
+
+#[test]
+fn test_influence_decays_and_propagates() {
+    let maze = test_maze();
+    let mut maps = InfluenceMaps::new(maze.width, maze.height, InfluenceConfig::default());
+
+    let sources = InfluenceSources {
+        enemies: vec![(1, 1)],
+        ..Default::default()
+    };
+    maps.update(&maze, &sources);
+    assert_eq!(*maps.enemy_presence.get((1, 1)), 1.);
+    assert_eq!(*maps.enemy_presence.get((2, 1)), 0.);
+
+    // the enemy leaves, its presence spreads out and fades away
+    maps.update(&maze, &InfluenceSources::default());
+    let (origin, neighbour) = (
+        *maps.enemy_presence.get((1, 1)),
+        *maps.enemy_presence.get((2, 1)),
+    );
+    assert!(origin < 1. && neighbour > 0. && neighbour < origin);
+    // never into walls
+    assert_eq!(*maps.enemy_presence.get((1, 0)), 0.);
+
+    for _ in 0..100 {
+        maps.update(&maze, &InfluenceSources::default());
+    }
+    assert!(*maps.enemy_presence.get((1, 1)) < 0.001);
+}
+
+#[test]
+fn test_influence_queries() {
+    let maze = test_maze();
+    let mut maps = InfluenceMaps::new(maze.width, maze.height, InfluenceConfig::default());
+
+    // the player walks right along the top corridor
+    for x in 1..=3 {
+        let sources = InfluenceSources {
+            player: Some((x, 1)),
+            ..Default::default()
+        };
+        maps.update(&maze, &sources);
+    }
+
+    // keeps going right, then follows the corridor down at the end
+    assert_eq!(maps.predicted_player_cell(&maze, 3), Some((6, 1)));
+    assert_eq!(maps.predicted_player_cell(&maze, 6), Some((7, 3)));
+
+    // the top corridor is in the line of fire, the bottom one isn't
+    let cover = maps.cover_cell(&maze, (7, 1), 3).unwrap();
+    assert_eq!(cover.1, 3);
+
+    let ambush = maps.ambush_cell(&maze, 6, 2).unwrap();
+    assert_eq!(ambush.1, 3);
+}
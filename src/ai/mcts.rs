@@ -0,0 +1,467 @@
+use crate::maze::{Coord, Maze, Symbol, SymbolConsts};
+use crate::util::{Array2D, Direction};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::rc::Rc;
+
+/// Cells a bullet travels each turn of the simulation.
+const BULLET_CELLS_PER_TURN: usize = 2;
+
+/// Who the search is picking moves for. The index of an enemy is its index in `SimState::enemies`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Agent {
+    Player,
+    Enemy(usize),
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Action {
+    Wait,
+    Move(Direction),
+    Shoot(Direction),
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SimBullet {
+    pub pos: Coord,
+    pub dir: Direction,
+}
+
+/// A lightweight, turn based copy of the game used for look-ahead.
+///
+/// Each turn the searching agent acts, everyone else follows a scripted policy (enemies chase the player,
+///  the player chases and shoots at the nearest enemy), then bullets advance.
+#[derive(Debug, Clone)]
+pub struct SimState {
+    // shared between all states of a search, walls don't change during a search
+    walls: Rc<Array2D<bool>>,
+    pub player: Coord,
+    // dead enemies are None so the indices of the others stay valid
+    pub enemies: Vec<Option<Coord>>,
+    pub bullets: Vec<SimBullet>,
+    pub turns_left: usize,
+}
+
+impl SimState {
+    pub fn new(maze: &Maze, player: Coord, enemies: Vec<Coord>, bullets: Vec<SimBullet>) -> Self {
+        let mut walls = Array2D::new(maze.width, maze.height, false);
+        for (coord, &symbol) in maze.iter_rows_first_enumerated() {
+            walls.set(coord, symbol == Symbol::BLOCKED);
+        }
+
+        Self {
+            walls: Rc::new(walls),
+            player,
+            enemies: enemies.into_iter().map(Some).collect(),
+            bullets,
+            turns_left: 0,
+        }
+    }
+
+    pub fn is_walkable(&self, coord: Coord) -> bool {
+        !*self.walls.get(coord)
+    }
+
+    fn step_coord(&self, coord: Coord, dir: Direction) -> Option<Coord> {
+        dir.step(coord, (self.walls.width, self.walls.height))
+            .filter(|&next| self.is_walkable(next))
+    }
+
+    pub fn enemies_alive(&self) -> usize {
+        self.enemies.iter().flatten().count()
+    }
+
+    pub fn is_terminal(&self, agent: Agent) -> bool {
+        let agent_dead = match agent {
+            Agent::Player => false,
+            Agent::Enemy(i) => self.enemies[i].is_none(),
+        };
+        self.turns_left == 0 || self.enemies_alive() == 0 || agent_dead
+    }
+
+    /// score of the state from the agent's point of view, in 0..=1
+    pub fn reward(&self, agent: Agent) -> f32 {
+        match agent {
+            Agent::Player => {
+                if self.enemies_alive() == 0 {
+                    1.
+                } else {
+                    let killed = self.enemies.len() - self.enemies_alive();
+                    0.5 * killed as f32 / self.enemies.len() as f32
+                }
+            }
+            Agent::Enemy(i) => {
+                if self.enemies[i].is_some() {
+                    1.
+                } else {
+                    0.
+                }
+            }
+        }
+    }
+
+    pub fn legal_actions(&self, agent: Agent) -> Vec<Action> {
+        let pos = match agent {
+            Agent::Player => self.player,
+            Agent::Enemy(i) => match self.enemies[i] {
+                Some(pos) => pos,
+                None => return Vec::new(),
+            },
+        };
+
+        let mut actions = vec![Action::Wait];
+        for dir in Direction::ALL {
+            if self.step_coord(pos, dir).is_some() {
+                actions.push(Action::Move(dir));
+                // only the player has a gun
+                if agent == Agent::Player {
+                    actions.push(Action::Shoot(dir));
+                }
+            }
+        }
+        actions
+    }
+
+    /// advances the simulation by one turn with the agent taking the given action
+    pub fn apply(&mut self, agent: Agent, action: Action) {
+        match agent {
+            Agent::Player => self.apply_player_action(action),
+            Agent::Enemy(_) => {
+                let action = self.scripted_player_action();
+                self.apply_player_action(action);
+            }
+        }
+
+        for i in 0..self.enemies.len() {
+            let enemy_action = match agent {
+                Agent::Enemy(searching) if searching == i => action,
+                _ => self.scripted_enemy_action(i),
+            };
+            self.apply_enemy_action(i, enemy_action);
+        }
+
+        self.advance_bullets();
+        self.turns_left = self.turns_left.saturating_sub(1);
+    }
+
+    fn apply_player_action(&mut self, action: Action) {
+        match action {
+            Action::Wait => {}
+            Action::Move(dir) => {
+                if let Some(next) = self.step_coord(self.player, dir) {
+                    self.player = next;
+                    self.resolve_contacts();
+                }
+            }
+            Action::Shoot(dir) => self.bullets.push(SimBullet {
+                pos: self.player,
+                dir,
+            }),
+        }
+    }
+
+    fn apply_enemy_action(&mut self, i: usize, action: Action) {
+        if let (Some(pos), Action::Move(dir)) = (self.enemies[i], action) {
+            if let Some(next) = self.step_coord(pos, dir) {
+                self.enemies[i] = Some(next);
+                self.resolve_contacts();
+            }
+        }
+    }
+
+    // enemies die when they touch the player, same as in the collision system
+    fn resolve_contacts(&mut self) {
+        let player = self.player;
+        for enemy in self.enemies.iter_mut() {
+            if *enemy == Some(player) {
+                *enemy = None;
+            }
+        }
+    }
+
+    fn advance_bullets(&mut self) {
+        let mut bullets = std::mem::take(&mut self.bullets);
+
+        bullets.retain_mut(|bullet| {
+            for _ in 0..BULLET_CELLS_PER_TURN {
+                match self.step_coord(bullet.pos, bullet.dir) {
+                    None => return false, // hit a wall
+                    Some(next) => bullet.pos = next,
+                }
+
+                if let Some(enemy) = self.enemies.iter_mut().find(|e| **e == Some(bullet.pos)) {
+                    *enemy = None;
+                    return false;
+                }
+            }
+            true
+        });
+
+        self.bullets = bullets;
+    }
+
+    /// moves along the axis with the largest distance to the target first, waits if both are blocked
+    fn greedy_step_towards(&self, from: Coord, to: Coord) -> Action {
+        let dx = to.0 as isize - from.0 as isize;
+        let dy = to.1 as isize - from.1 as isize;
+
+        let horizontal = match dx {
+            0 => None,
+            dx if dx > 0 => Some(Direction::Right),
+            _ => Some(Direction::Left),
+        };
+        let vertical = match dy {
+            0 => None,
+            dy if dy > 0 => Some(Direction::Up),
+            _ => Some(Direction::Down),
+        };
+
+        let preferred = if dx.abs() > dy.abs() {
+            [horizontal, vertical]
+        } else {
+            [vertical, horizontal]
+        };
+
+        preferred
+            .into_iter()
+            .flatten()
+            .find(|&dir| self.step_coord(from, dir).is_some())
+            .map(Action::Move)
+            .unwrap_or(Action::Wait)
+    }
+
+    fn scripted_enemy_action(&self, i: usize) -> Action {
+        match self.enemies[i] {
+            Some(pos) => self.greedy_step_towards(pos, self.player),
+            None => Action::Wait,
+        }
+    }
+
+    fn scripted_player_action(&self) -> Action {
+        // shoot any enemy in a clear line of fire
+        for dir in Direction::ALL {
+            let mut pos = self.player;
+            while let Some(next) = self.step_coord(pos, dir) {
+                if self.enemies.contains(&Some(next)) {
+                    return Action::Shoot(dir);
+                }
+                pos = next;
+            }
+        }
+
+        let manhattan = |(x, y): Coord| {
+            (x as isize - self.player.0 as isize).abs()
+                + (y as isize - self.player.1 as isize).abs()
+        };
+
+        match self.enemies.iter().flatten().min_by_key(|&&e| manhattan(e)) {
+            Some(&nearest) => self.greedy_step_towards(self.player, nearest),
+            None => Action::Wait,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MctsConfig {
+    /// search iterations run every time an agent picks a move
+    pub iterations_per_tick: usize,
+    /// UCT exploration constant
+    pub exploration: f32,
+    /// how many turns ahead the search looks
+    pub horizon: usize,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        Self {
+            iterations_per_tick: 500,
+            exploration: std::f32::consts::SQRT_2,
+            horizon: 20,
+        }
+    }
+}
+
+struct TreeNode {
+    state: SimState,
+    parent: Option<usize>,
+    action: Action,
+    children: Vec<usize>,
+    untried_actions: Vec<Action>,
+    visits: u32,
+    total_reward: f32,
+}
+
+impl TreeNode {
+    fn new(state: SimState, parent: Option<usize>, action: Action, agent: Agent) -> Self {
+        let untried_actions = if state.is_terminal(agent) {
+            Vec::new()
+        } else {
+            state.legal_actions(agent)
+        };
+
+        Self {
+            state,
+            parent,
+            action,
+            children: Vec::new(),
+            untried_actions,
+            visits: 0,
+            total_reward: 0.,
+        }
+    }
+
+    fn uct(&self, parent_visits: u32, exploration: f32) -> f32 {
+        let exploitation = self.total_reward / self.visits as f32;
+        let exploration = exploration * ((parent_visits as f32).ln() / self.visits as f32).sqrt();
+        exploitation + exploration
+    }
+}
+
+/// Runs Monte Carlo Tree Search from the given state and returns the action the agent should take.
+pub fn search<R: Rng>(
+    root_state: &SimState,
+    agent: Agent,
+    config: &MctsConfig,
+    rng: &mut R,
+) -> Action {
+    let mut root_state = root_state.clone();
+    root_state.turns_left = config.horizon;
+
+    // the tree is stored as an arena, nodes refer to each other by index
+    let mut tree = vec![TreeNode::new(root_state, None, Action::Wait, agent)];
+
+    for _ in 0..config.iterations_per_tick {
+        // selection
+        let mut current = 0;
+        while tree[current].untried_actions.is_empty() && !tree[current].children.is_empty() {
+            let parent_visits = tree[current].visits;
+            current = *tree[current]
+                .children
+                .iter()
+                .max_by(|&&a, &&b| {
+                    let a = tree[a].uct(parent_visits, config.exploration);
+                    let b = tree[b].uct(parent_visits, config.exploration);
+                    a.total_cmp(&b)
+                })
+                .unwrap();
+        }
+
+        // expansion
+        if !tree[current].untried_actions.is_empty() {
+            let untried = &mut tree[current].untried_actions;
+            let action = untried.swap_remove(rng.gen_range(0..untried.len()));
+
+            let mut state = tree[current].state.clone();
+            state.apply(agent, action);
+
+            tree.push(TreeNode::new(state, Some(current), action, agent));
+            let child = tree.len() - 1;
+            tree[current].children.push(child);
+            current = child;
+        }
+
+        // simulation
+        let mut state = tree[current].state.clone();
+        while !state.is_terminal(agent) {
+            let action = *state.legal_actions(agent).choose(rng).unwrap();
+            state.apply(agent, action);
+        }
+        let reward = state.reward(agent);
+
+        // backpropagation
+        let mut node = Some(current);
+        while let Some(i) = node {
+            tree[i].visits += 1;
+            tree[i].total_reward += reward;
+            node = tree[i].parent;
+        }
+    }
+
+    tree[0]
+        .children
+        .iter()
+        .max_by_key(|&&child| tree[child].visits)
+        .map(|&child| tree[child].action)
+        .unwrap_or(Action::Wait)
+}
+
+#[cfg(test)]
+fn maze_from_rows(rows: &[&str]) -> Maze {
+    Maze {
+        grid: Array2D::from(rows.join("\n") + "\n"),
+    }
+}
+
+#[test]
+fn test_mcts_player_walks_to_trapped_enemy() {
+    use rand::SeedableRng;
+
+    // the enemy can't chase the player out of its pocket, the only way to reach it within the
+    //  horizon is to go right along the bottom corridor
+    let maze = maze_from_rows(&[
+        "########", //
+        "#......#", //
+        "#.####.#", //
+        "#.#E.#.#", //
+        "#.##.#.#", //
+        "#P.....#", //
+        "########", //
+    ]);
+    let enemies = vec![(3, 3)];
+    let mut state = SimState::new(
+        &maze,
+        maze.player_spawn_coord().unwrap(),
+        enemies,
+        Vec::new(),
+    );
+    let config = MctsConfig {
+        iterations_per_tick: 3000,
+        horizon: 4,
+        ..Default::default()
+    };
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+
+    let first = search(&state, Agent::Player, &config, &mut rng);
+    assert_eq!(first, Action::Move(Direction::Right));
+
+    // keep deciding until the enemy is dead
+    state.turns_left = config.horizon;
+    while !state.is_terminal(Agent::Player) {
+        let action = search(&state, Agent::Player, &config, &mut rng);
+        state.apply(Agent::Player, action);
+    }
+    assert_eq!(state.enemies_alive(), 0);
+}
+
+#[test]
+fn test_mcts_boss_dodges_bullet() {
+    use rand::SeedableRng;
+
+    // the bullet reaches the boss this turn, only stepping into the alcove above it avoids it
+    let maze = maze_from_rows(&[
+        "########", //
+        "#......#", //
+        "#####.##", //
+        "#P######", //
+        "########", //
+    ]);
+    let bullet = SimBullet {
+        pos: (3, 1),
+        dir: Direction::Right,
+    };
+    let state = SimState::new(&maze, (1, 3), vec![(5, 1)], vec![bullet]);
+    let config = MctsConfig {
+        iterations_per_tick: 500,
+        horizon: 3,
+        ..Default::default()
+    };
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+
+    let action = search(&state, Agent::Enemy(0), &config, &mut rng);
+    assert_eq!(action, Action::Move(Direction::Up));
+
+    let mut next = state.clone();
+    next.turns_left = config.horizon;
+    next.apply(Agent::Enemy(0), action);
+    assert_eq!(next.enemies[0], Some((5, 2)));
+}
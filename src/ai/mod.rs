@@ -0,0 +1,17 @@
+mod director;
+pub use director::*;
+
+mod influence;
+pub use influence::*;
+
+mod mcts;
+pub use mcts::*;
+
+mod solvers;
+pub use solvers::*;
+
+mod squad;
+pub use squad::*;
+
+mod plugin;
+pub use plugin::*;
@@ -0,0 +1,706 @@
+use crate::ai::{
+    group_into_squads, lone_enemy_target, plan_flanking_routes, search, solve_headless, Action,
+    Agent, Blackboard, Director, InfluenceConfig, InfluenceMaps, InfluenceSources, MctsConfig,
+    SimBullet, SimState, SolverKind, Squad, Squads, StressSignals,
+};
+use crate::application::GameState;
+use crate::battle::{spawn_bullet, Bullet, EnemyKilled, PlayerHit};
+use crate::input::PlayerInputPlugin;
+use crate::maze::Coord;
+use crate::movement::{MovementSpeed, PathFollower, MOVEMENT_SYSTEM};
+use crate::util::pathfinding::{
+    smooth_path, DStarLite, PathRequests, PathfindingBudget, ReservationTable,
+};
+use crate::util::Direction;
+use crate::{fixed_time_step_dependant_state, Enemy, MazeResource, Player, Velocity};
+use bevy::ecs::schedule::ShouldRun;
+use bevy::log;
+use bevy::prelude::*;
+
+pub use components::*;
+mod components {
+    use crate::ai::{Action, MazeSolver, SolverKind};
+    use crate::maze::Coord;
+    use bevy::prelude::*;
+
+    /// entities with this component are driven by Monte Carlo Tree Search instead of their usual controls
+    #[derive(Debug, Component)]
+    pub struct MctsAgent {
+        pub next_action: Action,
+        // the cell the agent is currently moving to, a new action is picked once it gets there
+        pub target: Option<Coord>,
+    }
+
+    impl Default for MctsAgent {
+        fn default() -> Self {
+            Self {
+                next_action: Action::Wait,
+                target: None,
+            }
+        }
+    }
+
+    /// entities with this component walk the maze with one of the classic maze solvers
+    #[derive(Component)]
+    pub struct SolverAgent {
+        pub kind: SolverKind,
+        pub solver: Box<dyn MazeSolver + Send + Sync>,
+        // the cell the agent is currently moving to, the solver takes its next step once it gets there
+        pub target: Option<Coord>,
+    }
+}
+
+pub use resources::*;
+mod resources {
+    use crate::util::pathfinding::DStarLite;
+    use bevy::prelude::*;
+    use std::collections::HashMap;
+
+    /// incremental planners for the enemies, kept between maze changes so they can be repaired instead of
+    ///  searching from scratch
+    #[derive(Default)]
+    pub struct EnemyPlanners {
+        pub planners: HashMap<Entity, DStarLite>,
+    }
+}
+
+const SQUAD_SYSTEM: &str = "squad_system";
+const REQUEST_PATHS_SYSTEM: &str = "request_paths_system";
+
+pub struct AiPlugin;
+impl AiPlugin {
+    pub const DEPENDENCY: &'static str = "AiPlugin";
+}
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MctsConfig::default())
+            .insert_resource(Director::default())
+            .insert_resource(Squads::default())
+            .insert_resource(EnemyPlanners::default())
+            .insert_resource(PathfindingBudget::default())
+            .insert_resource(PathRequests::<Entity>::default())
+            .add_system_set(
+                SystemSet::on_enter(GameState::PlayGame)
+                    .with_system(reset_director_system)
+                    .with_system(reset_influence_maps_system)
+                    .with_system(reset_enemy_planners_system)
+                    .with_system(reset_path_requests_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::PlayGame)
+                    .after(PlayerInputPlugin::DEPENDENCY)
+                    .with_system(director_system)
+                    .with_system(squad_system.label(SQUAD_SYSTEM))
+                    .with_system(replan_on_maze_change_system.after(REQUEST_PATHS_SYSTEM))
+                    .with_system(
+                        request_paths_system
+                            .label(REQUEST_PATHS_SYSTEM)
+                            .after(SQUAD_SYSTEM),
+                    )
+                    .with_system(budgeted_pathfinding_system.after(REQUEST_PATHS_SYSTEM)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::PlayGame)
+                    .after(PlayerInputPlugin::DEPENDENCY)
+                    .with_system(toggle_player_bot_system)
+                    .with_system(cycle_player_solver_system),
+            )
+            .add_system_set(
+                SystemSet::new()
+                    .label(Self::DEPENDENCY)
+                    .with_run_criteria(fixed_time_step_dependant_state!(GameState::PlayGame))
+                    .with_system(mcts_decision_system)
+                    .with_system(update_influence_maps_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::PlayGame)
+                    .before(MOVEMENT_SYSTEM)
+                    .with_system(mcts_movement_system)
+                    .with_system(solver_movement_system),
+            );
+    }
+}
+
+/// B hands control of the player over to the search and back
+fn toggle_player_bot_system(
+    mut cmd: Commands,
+    input: Res<Input<KeyCode>>,
+    player: Query<(Entity, Option<&MctsAgent>), With<Player>>,
+) {
+    if !input.just_pressed(KeyCode::B) {
+        return;
+    }
+
+    for (entity, agent) in player.iter() {
+        if agent.is_some() {
+            log::info!("player bot disabled");
+            cmd.entity(entity).remove::<MctsAgent>();
+        } else {
+            log::info!("player bot enabled");
+            cmd.entity(entity)
+                .remove::<SolverAgent>()
+                .insert(MctsAgent::default());
+        }
+    }
+}
+
+/// M hands the player over to the next classic maze solver, heading for the first enemy spawn, and back
+///  to the player after the last one
+fn cycle_player_solver_system(
+    mut cmd: Commands,
+    input: Res<Input<KeyCode>>,
+    maze: Res<MazeResource>,
+    player: Query<(Entity, &Transform, Option<&SolverAgent>), With<Player>>,
+) {
+    if !input.just_pressed(KeyCode::M) {
+        return;
+    }
+    let (entity, transform, agent) = match player.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    let next_kind = match agent {
+        None => Some(SolverKind::ALL[0]),
+        Some(agent) => SolverKind::ALL
+            .iter()
+            .position(|&kind| kind == agent.kind)
+            .and_then(|i| SolverKind::ALL.get(i + 1).copied()),
+    };
+
+    let start = maze.maze_coord_from_world_pos(&transform.translation.truncate());
+    let goal = maze.enemy_spawn_coords().first().copied();
+    match (next_kind, goal) {
+        (Some(kind), Some(goal)) if maze.in_bounds(start) => {
+            // walk it without a window first, some solvers never get there. Trémaux walks every passage at
+            //  most twice
+            let mut preview = kind.new_solver(&maze, start, goal);
+            if !solve_headless(preview.as_mut(), &maze, 4 * maze.width * maze.height) {
+                log::warn!("{} won't reach the goal from here", preview.name());
+            }
+
+            let solver = kind.new_solver(&maze, start, goal);
+            log::info!("player solver: {}", solver.name());
+            cmd.entity(entity)
+                .remove::<MctsAgent>()
+                .insert(SolverAgent {
+                    kind,
+                    solver,
+                    target: None,
+                });
+        }
+        _ => {
+            log::info!("player solver disabled");
+            cmd.entity(entity).remove::<SolverAgent>();
+        }
+    }
+}
+
+fn solver_movement_system(
+    time: Res<Time>,
+    maze: Res<MazeResource>,
+    mut agents: Query<(&mut SolverAgent, &Transform, &mut Velocity, &MovementSpeed)>,
+) {
+    let dt = time.delta_seconds();
+
+    for (mut agent, transform, mut vel, movement_speed) in agents.iter_mut() {
+        vel.velocity = Vec2::ZERO;
+
+        if agent.target.is_none() {
+            if agent.solver.is_solved() || !agent.solver.step(&maze) {
+                continue;
+            }
+            agent.target = Some(agent.solver.position());
+        }
+
+        let target = match agent.target {
+            Some(target) => maze.screen_pos_from_maze_coord(target),
+            None => continue,
+        };
+        let to_target = target - transform.translation.truncate();
+        let step = movement_speed.0 * dt;
+
+        if to_target.length() <= step {
+            // arrived, snap to the center of the cell
+            vel.velocity = to_target;
+            agent.target = None;
+            if agent.solver.is_solved() {
+                log::info!(
+                    "{} reached the goal in {} steps",
+                    agent.solver.name(),
+                    agent.solver.trace().len() - 1
+                );
+            }
+        } else {
+            vel.velocity = to_target.normalize() * step;
+        }
+    }
+}
+
+fn mcts_decision_system(
+    maze: Res<MazeResource>,
+    config: Res<MctsConfig>,
+    player: Query<(Entity, &Transform), With<Player>>,
+    enemies: Query<(Entity, &Transform), With<Enemy>>,
+    bullets: Query<(&Transform, &Velocity), With<Bullet>>,
+    mut agents: Query<(Entity, &mut MctsAgent)>,
+) {
+    let (player_entity, player_transform) = match player.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    let coord_of =
+        |transform: &Transform| maze.maze_coord_from_world_pos(&transform.translation.truncate());
+
+    let player_coord = coord_of(player_transform);
+    if !maze.in_bounds(player_coord) {
+        return;
+    }
+
+    let (enemy_entities, enemy_coords): (Vec<Entity>, Vec<Coord>) = enemies
+        .iter()
+        .map(|(entity, transform)| (entity, coord_of(transform)))
+        .filter(|&(_, coord)| maze.in_bounds(coord))
+        .unzip();
+
+    let sim_bullets = bullets
+        .iter()
+        .filter_map(|(transform, velocity)| {
+            let pos = coord_of(transform);
+            let dir = Direction::from_vec2(velocity.velocity)?;
+            maze.in_bounds(pos).then_some(SimBullet { pos, dir })
+        })
+        .collect();
+
+    let state = SimState::new(&maze, player_coord, enemy_coords, sim_bullets);
+    let mut rng = rand::thread_rng();
+
+    for (entity, mut agent) in agents.iter_mut() {
+        // still on its way to the last chosen cell
+        if agent.target.is_some() {
+            continue;
+        }
+
+        let sim_agent = if entity == player_entity {
+            Agent::Player
+        } else if let Some(i) = enemy_entities.iter().position(|&e| e == entity) {
+            Agent::Enemy(i)
+        } else {
+            continue;
+        };
+
+        let from = match sim_agent {
+            Agent::Player => state.player,
+            Agent::Enemy(i) => state.enemies[i].unwrap(),
+        };
+
+        let action = search(&state, sim_agent, &config, &mut rng);
+        agent.next_action = action;
+        agent.target = match action {
+            Action::Move(dir) => dir.step(from, (maze.width, maze.height)),
+            _ => None,
+        };
+    }
+}
+
+fn mcts_movement_system(
+    mut cmd: Commands,
+    time: Res<Time>,
+    maze: Res<MazeResource>,
+    mut agents: Query<(&mut MctsAgent, &Transform, &mut Velocity, &MovementSpeed)>,
+) {
+    let dt = time.delta_seconds();
+
+    for (mut agent, transform, mut vel, movement_speed) in agents.iter_mut() {
+        vel.velocity = Vec2::ZERO;
+        let pos = transform.translation.truncate();
+
+        match agent.next_action {
+            Action::Wait => {}
+            Action::Shoot(dir) => {
+                spawn_bullet(&mut cmd, &maze, pos, dir.to_vec2());
+                agent.next_action = Action::Wait;
+            }
+            Action::Move(_) => {
+                let target = match agent.target {
+                    Some(target) => maze.screen_pos_from_maze_coord(target),
+                    None => continue,
+                };
+
+                let to_target = target - pos;
+                let step = movement_speed.0 * dt;
+
+                if to_target.length() <= step {
+                    // arrived, snap to the center of the cell
+                    vel.velocity = to_target;
+                    agent.next_action = Action::Wait;
+                    agent.target = None;
+                } else {
+                    vel.velocity = to_target.normalize() * step;
+                }
+            }
+        }
+    }
+}
+
+fn reset_director_system(mut director: ResMut<Director>) {
+    director.reset();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn director_system(
+    mut cmd: Commands,
+    time: Res<Time>,
+    maze: Res<MazeResource>,
+    mut director: ResMut<Director>,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    mut player_hit_events: EventReader<PlayerHit>,
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<&Transform, With<Enemy>>,
+) {
+    let player_transform = match player.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let player_coord = maze.maze_coord_from_world_pos(&player_transform.translation.truncate());
+
+    let enemy_coords: Vec<Coord> = enemies
+        .iter()
+        .map(|transform| maze.maze_coord_from_world_pos(&transform.translation.truncate()))
+        .collect();
+
+    let radius = director.config.nearby_radius;
+    let signals = StressSignals {
+        hits_taken: player_hit_events.iter().count() as u32,
+        kills: enemy_killed_events.iter().count() as u32,
+        enemies_nearby: enemy_coords
+            .iter()
+            .filter(|(x, y)| x.abs_diff(player_coord.0) + y.abs_diff(player_coord.1) <= radius)
+            .count(),
+    };
+
+    if !director.update(time.delta(), &signals) {
+        return;
+    }
+
+    if player_coord.0 >= maze.width || player_coord.1 >= maze.height {
+        return;
+    }
+
+    if let Some(spawn) = director.choose_spawn(&maze, player_coord, &enemy_coords) {
+        log::info!("director: activating enemy spawn at {:?}", spawn);
+        Enemy::spawn(&mut cmd, maze.screen_pos_from_maze_coord(spawn));
+    }
+}
+
+/// Regroups enemies into squads now and then, and replans the routes of a squad whenever its target changes.
+/// Squads close in on the player, lone enemies pick their target from the influence maps.
+fn squad_system(
+    time: Res<Time>,
+    maze: Res<MazeResource>,
+    maps: Option<Res<InfluenceMaps>>,
+    mut squads: ResMut<Squads>,
+    player: Query<&Transform, With<Player>>,
+    mut enemies: Query<(Entity, &Transform, &mut PathFollower), With<Enemy>>,
+) {
+    let player_transform = match player.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    let player = maze.maze_coord_from_world_pos(&player_transform.translation.truncate());
+    if !maze.in_bounds(player) {
+        return;
+    }
+
+    let (entities, positions): (Vec<Entity>, Vec<Coord>) = enemies
+        .iter()
+        .map(|(entity, transform, _)| {
+            (
+                entity,
+                maze.maze_coord_from_world_pos(&transform.translation.truncate()),
+            )
+        })
+        .filter(|&(_, coord)| maze.in_bounds(coord))
+        .unzip();
+
+    squads.time_since_regroup += time.delta();
+    let member_count: usize = squads.squads.iter().map(|squad| squad.members.len()).sum();
+
+    if squads.time_since_regroup >= squads.config.regroup_interval || member_count != entities.len()
+    {
+        squads.time_since_regroup = Default::default();
+        squads.squads = group_into_squads(
+            &positions,
+            squads.config.join_radius,
+            squads.config.max_squad_size,
+        )
+        .into_iter()
+        .map(|indices| Squad {
+            members: indices.into_iter().map(|i| entities[i]).collect(),
+            blackboard: Blackboard::default(),
+        })
+        .collect();
+    }
+
+    let config = squads.config.clone();
+    // (squad, starts of its members, target) of the squads whose target changed
+    let mut replans = Vec::new();
+    for (i, squad) in squads.squads.iter().enumerate() {
+        let starts: Vec<Coord> = squad
+            .members
+            .iter()
+            .filter_map(|member| entities.iter().position(|e| e == member))
+            .map(|i| positions[i])
+            .collect();
+        if starts.len() != squad.members.len() {
+            // a member died, it's regrouped next frame
+            continue;
+        }
+
+        let target = match (&maps, starts.as_slice()) {
+            (Some(maps), &[start]) => lone_enemy_target(maps, &maze, &config, start, player),
+            _ => player,
+        };
+        if squad.blackboard.target != Some(target) {
+            replans.push((i, starts, target));
+        }
+    }
+    if replans.is_empty() {
+        return;
+    }
+
+    // squads stay out of each other's way too. Enemies reaching the player die, they don't block the target
+    let mut reservations =
+        ReservationTable::new(config.reservation_horizon).without_holding_goals();
+    // the routes kept from earlier frames, from where their members are now
+    for (i, squad) in squads.squads.iter().enumerate() {
+        if replans.iter().any(|&(replanned, _, _)| replanned == i) {
+            continue;
+        }
+        for &member in &squad.members {
+            if let Ok((_, _, path_follower)) = enemies.get(member) {
+                reservations.reserve_path(path_follower.remaining());
+            }
+        }
+    }
+
+    for (i, starts, target) in replans {
+        let squad = &mut squads.squads[i];
+        squad.blackboard.target = Some(target);
+        squad.blackboard.routes = plan_flanking_routes(
+            &maze,
+            &mut reservations,
+            &starts,
+            target,
+            config.corridor_penalty,
+        );
+
+        for (member, route) in squad.members.iter().zip(squad.blackboard.routes.iter()) {
+            if let Ok((_, _, mut path_follower)) = enemies.get_mut(*member) {
+                *path_follower = PathFollower::new(route.clone().unwrap_or_default());
+            }
+        }
+    }
+}
+
+fn reset_influence_maps_system(mut cmd: Commands, maze: Res<MazeResource>) {
+    cmd.insert_resource(InfluenceMaps::new(
+        maze.width,
+        maze.height,
+        InfluenceConfig::default(),
+    ));
+}
+
+fn update_influence_maps_system(
+    maze: Res<MazeResource>,
+    maps: Option<ResMut<InfluenceMaps>>,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<&Transform, With<Enemy>>,
+    bullets: Query<&Transform, With<Bullet>>,
+) {
+    let mut maps = match maps {
+        Some(maps) => maps,
+        None => return,
+    };
+
+    let coord_of = |pos: Vec2| {
+        let coord = maze.maze_coord_from_world_pos(&pos);
+        maze.in_bounds(coord).then_some(coord)
+    };
+
+    let sources = InfluenceSources {
+        player: player
+            .get_single()
+            .ok()
+            .and_then(|transform| coord_of(transform.translation.truncate())),
+        enemies: enemies
+            .iter()
+            .filter_map(|transform| coord_of(transform.translation.truncate()))
+            .collect(),
+        bullets: bullets
+            .iter()
+            .filter_map(|transform| coord_of(transform.translation.truncate()))
+            .collect(),
+        kills: enemy_killed_events
+            .iter()
+            .filter_map(|killed| coord_of(killed.position))
+            .collect(),
+    };
+
+    maps.update(&maze, &sources);
+}
+
+fn reset_enemy_planners_system(
+    mut planners: ResMut<EnemyPlanners>,
+    mut maze: ResMut<MazeResource>,
+) {
+    planners.planners.clear();
+    // changes made while building the map don't concern anyone
+    maze.changed_coords.clear();
+}
+
+/// When cells of the maze change, repairs the path of every enemy whose path crosses them with D* Lite instead
+///  of a new search. Enemies whose target moved since their last repair get a new planner.
+fn replan_on_maze_change_system(
+    mut maze: ResMut<MazeResource>,
+    mut planners: ResMut<EnemyPlanners>,
+    player: Query<&Transform, With<Player>>,
+    mut enemies: Query<(Entity, &Transform, &mut PathFollower), With<Enemy>>,
+) {
+    if maze.changed_coords.is_empty() {
+        return;
+    }
+    let changed = std::mem::take(&mut maze.changed_coords);
+
+    let player_transform = match player.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    let target = maze.maze_coord_from_world_pos(&player_transform.translation.truncate());
+    if !maze.in_bounds(target) {
+        return;
+    }
+
+    // forget the enemies that died
+    planners
+        .planners
+        .retain(|&entity, _| enemies.get(entity).is_ok());
+
+    for (entity, transform, mut path_follower) in enemies.iter_mut() {
+        let position = maze.maze_coord_from_world_pos(&transform.translation.truncate());
+        if !maze.in_bounds(position) {
+            continue;
+        }
+
+        // keep squad routes that aren't in the way of the change, a planner that missed changes is outdated
+        let remaining = path_follower.remaining();
+        let crosses_change = remaining.iter().any(|cell| changed.contains(cell))
+            || remaining
+                .windows(2)
+                .any(|segment| !maze.line_of_sight(segment[0], segment[1]));
+        if !crosses_change {
+            planners.planners.remove(&entity);
+            continue;
+        }
+
+        let planner = planners
+            .planners
+            .entry(entity)
+            .and_modify(|planner| {
+                if planner.goal() == target {
+                    planner.move_start(&maze, position);
+                    planner.update_cells(&maze, &changed);
+                } else {
+                    *planner = DStarLite::new(&maze, position, target);
+                }
+            })
+            .or_insert_with(|| DStarLite::new(&maze, position, target));
+
+        // the enemy is alone on this path, it can cut straight through open areas
+        let path = planner
+            .path(&maze)
+            .map(|path| smooth_path(&maze, &path))
+            .unwrap_or_default();
+        *path_follower = PathFollower::new(path);
+    }
+}
+
+fn reset_path_requests_system(mut requests: ResMut<PathRequests<Entity>>) {
+    *requests = PathRequests::default();
+}
+
+/// Enemies at the end of their path that haven't reached the player yet ask for a new one
+fn request_paths_system(
+    maze: Res<MazeResource>,
+    mut requests: ResMut<PathRequests<Entity>>,
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<(Entity, &Transform, &PathFollower), With<Enemy>>,
+) {
+    // forget the enemies that died
+    requests.retain(|&entity| enemies.get(entity).is_ok());
+    // the searches so far went through the old maze, the changes are taken by the replanning after this
+    if !maze.changed_coords.is_empty() {
+        requests.restart(&maze);
+    }
+
+    let player_transform = match player.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    let target = maze.maze_coord_from_world_pos(&player_transform.translation.truncate());
+    if !maze.in_bounds(target) {
+        return;
+    }
+
+    for (entity, transform, path_follower) in enemies.iter() {
+        let position = maze.maze_coord_from_world_pos(&transform.translation.truncate());
+        let next_to_player = position.0.abs_diff(target.0) + position.1.abs_diff(target.1) <= 1;
+
+        if path_follower.current_waypoint().is_some()
+            || next_to_player
+            || !maze.in_bounds(position)
+            || requests.is_pending(&entity)
+        {
+            continue;
+        }
+        requests.request(&maze, entity, position, target);
+    }
+}
+
+/// Works on the queued path requests within the budget of a frame, the enemies closest to the player first
+fn budgeted_pathfinding_system(
+    maze: Res<MazeResource>,
+    budget: Res<PathfindingBudget>,
+    mut requests: ResMut<PathRequests<Entity>>,
+    player: Query<&Transform, With<Player>>,
+    mut enemies: Query<&mut PathFollower, With<Enemy>>,
+) {
+    if requests.is_empty() {
+        return;
+    }
+    let player_transform = match player.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let focus = maze.maze_coord_from_world_pos(&player_transform.translation.truncate());
+
+    for (entity, path) in requests.run(&maze, &budget, focus) {
+        if let Ok(mut path_follower) = enemies.get_mut(entity) {
+            // someone else gave it a path in the meantime
+            if path_follower.current_waypoint().is_some() {
+                continue;
+            }
+            // requested paths aren't shared with a squad, so they don't have to stay on the grid lines
+            let path = path
+                .map(|path| smooth_path(&maze, &path))
+                .unwrap_or_default();
+            *path_follower = PathFollower::new(path);
+        }
+    }
+}
@@ -0,0 +1,492 @@
+use crate::maze::{Coord, Maze};
+use crate::util::pathfinding::{reconstruct_path, walkable_neighbours};
+use crate::util::Direction;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// A classic maze solving strategy that walks the maze one cell at a time, without knowing the layout up
+///  front (dead-end filling excepted).
+pub trait MazeSolver {
+    fn name(&self) -> &'static str;
+
+    fn position(&self) -> Coord;
+
+    fn goal(&self) -> Coord;
+
+    /// Moves to a neighbouring cell. Returns false if the solver can't move anymore.
+    fn step(&mut self, maze: &Maze) -> bool;
+
+    /// every cell visited so far in order, the start included
+    fn trace(&self) -> &[Coord];
+
+    fn is_solved(&self) -> bool {
+        self.position() == self.goal()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SolverKind {
+    WallFollower,
+    Pledge,
+    Tremaux,
+    DeadEndFilling,
+}
+
+impl SolverKind {
+    pub const ALL: [Self; 4] = [
+        Self::WallFollower,
+        Self::Pledge,
+        Self::Tremaux,
+        Self::DeadEndFilling,
+    ];
+
+    pub fn new_solver(
+        self,
+        maze: &Maze,
+        start: Coord,
+        goal: Coord,
+    ) -> Box<dyn MazeSolver + Send + Sync> {
+        match self {
+            Self::WallFollower => Box::new(WallFollower::new(start, goal)),
+            Self::Pledge => Box::new(Pledge::new(start, goal)),
+            Self::Tremaux => Box::new(Tremaux::new(start, goal)),
+            Self::DeadEndFilling => Box::new(DeadEndFilling::new(maze, start, goal)),
+        }
+    }
+}
+
+/// Steps the solver until it reaches the goal, gets stuck or has taken `max_steps` steps.
+/// Returns true if it reached the goal.
+pub fn solve_headless(solver: &mut dyn MazeSolver, maze: &Maze, max_steps: usize) -> bool {
+    for _ in 0..max_steps {
+        if solver.is_solved() || !solver.step(maze) {
+            break;
+        }
+    }
+    solver.is_solved()
+}
+
+fn open_towards(maze: &Maze, cell: Coord, dir: Direction) -> Option<Coord> {
+    dir.step(cell, (maze.width, maze.height))
+        .filter(|&next| maze.is_walkable(next))
+}
+
+/// Keeps its right hand on the wall. Solves mazes where the goal is connected to the outer wall, but walks in
+///  circles around islands of walls.
+#[derive(Debug)]
+pub struct WallFollower {
+    goal: Coord,
+    heading: Direction,
+    trace: Vec<Coord>,
+}
+
+impl WallFollower {
+    pub fn new(start: Coord, goal: Coord) -> Self {
+        Self {
+            goal,
+            heading: Direction::Up,
+            trace: vec![start],
+        }
+    }
+}
+
+impl MazeSolver for WallFollower {
+    fn name(&self) -> &'static str {
+        "Wall follower"
+    }
+
+    fn position(&self) -> Coord {
+        *self.trace.last().unwrap()
+    }
+
+    fn goal(&self) -> Coord {
+        self.goal
+    }
+
+    fn step(&mut self, maze: &Maze) -> bool {
+        let position = self.position();
+        let h = self.heading;
+
+        // right, straight, left, back
+        let turns = [h.clockwise(), h, h.counter_clockwise(), h.opposite()];
+        match turns
+            .into_iter()
+            .find_map(|dir| open_towards(maze, position, dir).map(|next| (dir, next)))
+        {
+            Some((dir, next)) => {
+                self.heading = dir;
+                self.trace.push(next);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn trace(&self) -> &[Coord] {
+        &self.trace
+    }
+}
+
+/// Heads in the main direction (towards the goal from the start, picked once) until it hits a wall, then
+///  follows the wall (right hand) until it faces the main direction again with all turns on the way summing to
+///  zero. Gets away from islands the wall follower circles forever.
+#[derive(Debug)]
+pub struct Pledge {
+    goal: Coord,
+    main_direction: Direction,
+    heading: Direction,
+    // +1 for every right turn, -1 for every left turn while following a wall, 0 when not following one
+    turns: i32,
+    following_wall: bool,
+    trace: Vec<Coord>,
+}
+
+impl Pledge {
+    pub fn new(start: Coord, goal: Coord) -> Self {
+        let main_direction = Self::main_direction(start, goal);
+        Self {
+            goal,
+            main_direction,
+            heading: main_direction,
+            turns: 0,
+            following_wall: false,
+            trace: vec![start],
+        }
+    }
+
+    fn main_direction(from: Coord, to: Coord) -> Direction {
+        let (dx, dy) = (to.0 as f32 - from.0 as f32, to.1 as f32 - from.1 as f32);
+        Direction::from_vec2(bevy::math::Vec2::new(dx, dy)).unwrap_or(Direction::Up)
+    }
+}
+
+impl MazeSolver for Pledge {
+    fn name(&self) -> &'static str {
+        "Pledge"
+    }
+
+    fn position(&self) -> Coord {
+        *self.trace.last().unwrap()
+    }
+
+    fn goal(&self) -> Coord {
+        self.goal
+    }
+
+    fn step(&mut self, maze: &Maze) -> bool {
+        let position = self.position();
+
+        if !self.following_wall {
+            if let Some(next) = open_towards(maze, position, self.heading) {
+                self.trace.push(next);
+                return true;
+            }
+
+            // hit a wall, turn left until free with the wall on the right
+            self.following_wall = true;
+            for _ in 0..3 {
+                self.heading = self.heading.counter_clockwise();
+                self.turns -= 1;
+                if let Some(next) = open_towards(maze, position, self.heading) {
+                    self.trace.push(next);
+                    return true;
+                }
+            }
+            return false;
+        }
+
+        let h = self.heading;
+        let turns = [
+            (h.clockwise(), 1),
+            (h, 0),
+            (h.counter_clockwise(), -1),
+            (h.opposite(), -2),
+        ];
+        let (dir, turn, next) = match turns
+            .into_iter()
+            .find_map(|(dir, turn)| open_towards(maze, position, dir).map(|next| (dir, turn, next)))
+        {
+            Some(step) => step,
+            None => return false,
+        };
+
+        self.heading = dir;
+        self.turns += turn;
+        self.trace.push(next);
+
+        // facing the main direction again after unwinding every turn
+        if self.turns == 0 {
+            debug_assert_eq!(self.heading, self.main_direction);
+            self.following_wall = false;
+        }
+        true
+    }
+
+    fn trace(&self) -> &[Coord] {
+        &self.trace
+    }
+}
+
+/// Marks every passage it walks through. Prefers unmarked passages, turns back when a new passage leads to a
+///  cell it has already been in, and never walks a passage a third time. Solves every maze, the passages
+///  marked once form a path back to the start.
+#[derive(Debug)]
+pub struct Tremaux {
+    goal: Coord,
+    // times each passage between two cells was walked, keyed with the smaller cell first
+    marks: HashMap<(Coord, Coord), u8>,
+    visited: HashSet<Coord>,
+    // the current cell had been visited before the last step
+    revisited: bool,
+    trace: Vec<Coord>,
+}
+
+impl Tremaux {
+    pub fn new(start: Coord, goal: Coord) -> Self {
+        Self {
+            goal,
+            marks: HashMap::new(),
+            visited: HashSet::from([start]),
+            revisited: false,
+            trace: vec![start],
+        }
+    }
+
+    fn passage(a: Coord, b: Coord) -> (Coord, Coord) {
+        if a < b {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    }
+
+    fn marks(&self, a: Coord, b: Coord) -> u8 {
+        self.marks.get(&Self::passage(a, b)).copied().unwrap_or(0)
+    }
+}
+
+impl MazeSolver for Tremaux {
+    fn name(&self) -> &'static str {
+        "Trémaux"
+    }
+
+    fn position(&self) -> Coord {
+        *self.trace.last().unwrap()
+    }
+
+    fn goal(&self) -> Coord {
+        self.goal
+    }
+
+    fn step(&mut self, maze: &Maze) -> bool {
+        let position = self.position();
+        let previous = self.trace.len().checked_sub(2).map(|i| self.trace[i]);
+
+        // came through a new passage into a cell seen before, go back the same way
+        let next = match previous {
+            Some(previous) if self.revisited && self.marks(previous, position) == 1 => {
+                Some(previous)
+            }
+            _ => walkable_neighbours(maze, position)
+                .filter(|&next| self.marks(position, next) < 2)
+                // unmarked first, and don't turn around if there's anything else
+                .min_by_key(|&next| (self.marks(position, next), Some(next) == previous)),
+        };
+
+        match next {
+            Some(next) => {
+                *self.marks.entry(Self::passage(position, next)).or_default() += 1;
+                self.revisited = !self.visited.insert(next);
+                self.trace.push(next);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn trace(&self) -> &[Coord] {
+        &self.trace
+    }
+}
+
+/// Looks at the whole maze first and fills in every dead end (except the start and goal) until only the
+///  cells that lead somewhere are left, then walks through them.
+#[derive(Debug)]
+pub struct DeadEndFilling {
+    goal: Coord,
+    // the cells left to walk
+    route: VecDeque<Coord>,
+    trace: Vec<Coord>,
+}
+
+impl DeadEndFilling {
+    pub fn new(maze: &Maze, start: Coord, goal: Coord) -> Self {
+        let mut is_filled = HashSet::new();
+
+        let open_neighbours = |cell: Coord, is_filled: &HashSet<Coord>| {
+            walkable_neighbours(maze, cell)
+                .filter(|neighbour| !is_filled.contains(neighbour))
+                .count()
+        };
+
+        let mut queue: VecDeque<Coord> = maze
+            .iter_rows_first_enumerated()
+            .map(|(coord, _)| coord)
+            .filter(|&coord| maze.is_walkable(coord))
+            .collect();
+        while let Some(cell) = queue.pop_front() {
+            if cell == start || cell == goal || is_filled.contains(&cell) {
+                continue;
+            }
+            if open_neighbours(cell, &is_filled) <= 1 {
+                is_filled.insert(cell);
+                // filling this one may have made a dead end of its neighbour
+                queue.extend(walkable_neighbours(maze, cell));
+            }
+        }
+
+        // loops and open areas survive the filling, take the shortest way through what's left
+        let route = Self::route_through(maze, &is_filled, start, goal);
+
+        Self {
+            goal,
+            route: route.into_iter().skip(1).collect(),
+            trace: vec![start],
+        }
+    }
+
+    fn route_through(
+        maze: &Maze,
+        filled: &HashSet<Coord>,
+        start: Coord,
+        goal: Coord,
+    ) -> Vec<Coord> {
+        let mut parents = HashMap::new();
+        let mut visited = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(cell) = queue.pop_front() {
+            if cell == goal {
+                return reconstruct_path(&parents, goal);
+            }
+            for next in walkable_neighbours(maze, cell) {
+                if !filled.contains(&next) && visited.insert(next) {
+                    parents.insert(next, cell);
+                    queue.push_back(next);
+                }
+            }
+        }
+        Vec::new()
+    }
+}
+
+impl MazeSolver for DeadEndFilling {
+    fn name(&self) -> &'static str {
+        "Dead-end filling"
+    }
+
+    fn position(&self) -> Coord {
+        *self.trace.last().unwrap()
+    }
+
+    fn goal(&self) -> Coord {
+        self.goal
+    }
+
+    fn step(&mut self, _maze: &Maze) -> bool {
+        match self.route.pop_front() {
+            Some(next) => {
+                self.trace.push(next);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn trace(&self) -> &[Coord] {
+        &self.trace
+    }
+}
+
+#[cfg(test)]
+fn test_maze() -> Maze {
+    use crate::util::Array2D;
+
+    // a perfect maze with a few dead ends, start in the bottom left, goal in the top right
+    Maze {
+        grid: Array2D::from(
+            "\
+            #########\n\
+            #.#.....#\n\
+            #.#.###.#\n\
+            #...#...#\n\
+            ###.#.###\n\
+            #...#...#\n\
+            #########\n"
+                .to_string(),
+        ),
+    }
+}
+
+#[test]
+fn test_solvers_reach_the_goal() {
+    let maze = test_maze();
+    let (start, goal) = ((1, 1), (7, 5));
+
+    for kind in SolverKind::ALL {
+        let mut solver = kind.new_solver(&maze, start, goal);
+        assert!(
+            solve_headless(solver.as_mut(), &maze, 500),
+            "{}",
+            solver.name()
+        );
+
+        let trace = solver.trace();
+        assert_eq!((trace[0], trace[trace.len() - 1]), (start, goal));
+        assert!(trace
+            .windows(2)
+            .all(|w| w[0].0.abs_diff(w[1].0) + w[0].1.abs_diff(w[1].1) == 1));
+    }
+}
+
+#[test]
+fn test_dead_end_filling_walks_the_solution() {
+    let maze = test_maze();
+    let (start, goal) = ((1, 1), (7, 5));
+
+    let mut solver = DeadEndFilling::new(&maze, start, goal);
+    solve_headless(&mut solver, &maze, 500);
+
+    // a perfect maze has a single solution, every dead end gets filled and the solution is walked straight
+    assert_eq!(
+        solver.trace(),
+        crate::util::pathfinding::a_star(&maze, start, goal).unwrap()
+    );
+    assert!(!solver.trace().contains(&(1, 5)));
+}
+
+#[test]
+fn test_pledge_escapes_an_island() {
+    use crate::util::Array2D;
+
+    // starting next to the pillar, the wall follower walks in a small circle forever
+    let maze = Maze {
+        grid: Array2D::from(
+            "\
+            #########\n\
+            #.......#\n\
+            #.......#\n\
+            #..#....#\n\
+            #.......#\n\
+            #.......#\n\
+            #########\n"
+                .to_string(),
+        ),
+    };
+    let (start, goal) = ((5, 3), (1, 3));
+
+    let mut wall_follower = WallFollower::new(start, goal);
+    assert!(!solve_headless(&mut wall_follower, &maze, 200));
+
+    let mut pledge = Pledge::new(start, goal);
+    assert!(solve_headless(&mut pledge, &maze, 200));
+}
@@ -0,0 +1,283 @@
+use crate::ai::InfluenceMaps;
+use crate::maze::{Coord, Maze};
+use crate::util::pathfinding::{self, ReservationTable};
+use bevy::prelude::Entity;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct SquadConfig {
+    /// enemies closer than this (in cells) to a squad member join the squad
+    pub join_radius: usize,
+    pub max_squad_size: usize,
+    /// extra cost of entering a cell another squad member already routes through or next to
+    pub corridor_penalty: u32,
+    pub regroup_interval: Duration,
+    /// timesteps (cells walked) the routes of all squads are kept free of collisions for
+    pub reservation_horizon: usize,
+    /// a lone enemy this close (in cells) to the player goes straight for it
+    pub charge_distance: usize,
+    /// a lone enemy standing in at least this much danger takes cover
+    pub cover_danger: f32,
+    /// how far (in steps) a lone enemy looks for cover
+    pub cover_steps: usize,
+    /// how far ahead (in cells) of the player a lone enemy lies in wait
+    pub ambush_lookahead: usize,
+    pub ambush_radius: usize,
+}
+
+impl Default for SquadConfig {
+    fn default() -> Self {
+        Self {
+            join_radius: 4,
+            max_squad_size: 4,
+            corridor_penalty: 8,
+            regroup_interval: Duration::from_secs(2),
+            reservation_horizon: 48,
+            charge_distance: 3,
+            cover_danger: 0.6,
+            cover_steps: 4,
+            ambush_lookahead: 4,
+            ambush_radius: 2,
+        }
+    }
+}
+
+/// Knowledge shared between the members of a squad.
+#[derive(Debug, Default)]
+pub struct Blackboard {
+    /// the cell the squad is closing in on
+    pub target: Option<Coord>,
+    /// the route planned for each member, in the same order as `Squad::members`
+    pub routes: Vec<Option<Vec<Coord>>>,
+}
+
+#[derive(Debug, Default)]
+pub struct Squad {
+    pub members: Vec<Entity>,
+    pub blackboard: Blackboard,
+}
+
+#[derive(Debug, Default)]
+pub struct Squads {
+    pub config: SquadConfig,
+    pub squads: Vec<Squad>,
+    pub time_since_regroup: Duration,
+}
+
+/// Groups agents that are close to each other, returns the indices of the agents in each group.
+pub fn group_into_squads(
+    positions: &[Coord],
+    join_radius: usize,
+    max_squad_size: usize,
+) -> Vec<Vec<usize>> {
+    let distance = |a: Coord, b: Coord| a.0.abs_diff(b.0) + a.1.abs_diff(b.1);
+
+    let mut assigned = vec![false; positions.len()];
+    let mut squads = Vec::new();
+
+    for first in 0..positions.len() {
+        if assigned[first] {
+            continue;
+        }
+        assigned[first] = true;
+        let mut squad = vec![first];
+
+        // grow the squad from its members, so chains of nearby agents end up together
+        let mut i = 0;
+        while i < squad.len() && squad.len() < max_squad_size {
+            let member = positions[squad[i]];
+            for other in 0..positions.len() {
+                if squad.len() >= max_squad_size {
+                    break;
+                }
+                if !assigned[other] && distance(member, positions[other]) <= join_radius {
+                    assigned[other] = true;
+                    squad.push(other);
+                }
+            }
+            i += 1;
+        }
+
+        squads.push(squad);
+    }
+
+    squads
+}
+
+/// Where an enemy without a squad heads for: the player once it's close, cover while it's in the player's
+///  line of fire, and otherwise a spot to ambush the player from.
+pub fn lone_enemy_target(
+    maps: &InfluenceMaps,
+    maze: &Maze,
+    config: &SquadConfig,
+    position: Coord,
+    player: Coord,
+) -> Coord {
+    let distance = position.0.abs_diff(player.0) + position.1.abs_diff(player.1);
+    if distance <= config.charge_distance {
+        return player;
+    }
+
+    if maps.danger_at(position) >= config.cover_danger {
+        if let Some(cover) = maps.cover_cell(maze, position, config.cover_steps) {
+            return cover;
+        }
+    }
+
+    maps.ambush_cell(maze, config.ambush_lookahead, config.ambush_radius)
+        .unwrap_or(player)
+}
+
+/// Routes from each start to the target that avoid each other's corridors, so the squad surrounds the target
+///  instead of queueing up behind each other. Members closest to the target get the most direct routes.
+///
+/// Routes have one cell per timestep and don't collide with each other or anything already in the
+///  reservation table, they're added to it. Members are never routed through the cell another member is
+///  still standing in.
+pub fn plan_flanking_routes(
+    maze: &Maze,
+    reservations: &mut ReservationTable,
+    starts: &[Coord],
+    target: Coord,
+    corridor_penalty: u32,
+) -> Vec<Option<Vec<Coord>>> {
+    let distance = |a: Coord, b: Coord| a.0.abs_diff(b.0) + a.1.abs_diff(b.1);
+
+    let mut order: Vec<usize> = (0..starts.len()).collect();
+    order.sort_by_key(|&i| distance(starts[i], target));
+
+    // how many of the already planned routes pass through or right next to each cell
+    let mut claimed: HashMap<Coord, u32> = HashMap::new();
+    let mut routes = vec![None; starts.len()];
+
+    for i in order {
+        // the members without a route yet stand still until they get one, the others start from their cell
+        let mut with_members = reservations.clone();
+        for (j, &start) in starts.iter().enumerate() {
+            if j != i {
+                with_members.reserve_cell(start, 0);
+                if routes[j].is_none() {
+                    with_members.reserve_cell(start, 1);
+                }
+            }
+        }
+
+        let route = pathfinding::cooperative_a_star_with_cost(
+            maze,
+            &with_members,
+            starts[i],
+            target,
+            |cell| {
+                // everyone has to converge on the target in the end
+                if distance(cell, target) <= 1 {
+                    return 1;
+                }
+                1 + corridor_penalty * claimed.get(&cell).copied().unwrap_or(0)
+            },
+        );
+
+        if let Some(route) = &route {
+            reservations.reserve_path(route);
+            for &cell in route {
+                *claimed.entry(cell).or_default() += 1;
+                for neighbour in pathfinding::walkable_neighbours(maze, cell) {
+                    *claimed.entry(neighbour).or_default() += 1;
+                }
+            }
+        }
+        routes[i] = route;
+    }
+
+    routes
+}
+
+#[test]
+fn test_group_into_squads() {
+    let positions = [(1, 1), (3, 1), (5, 1), (20, 20), (21, 20)];
+
+    let squads = group_into_squads(&positions, 2, 4);
+    assert_eq!(squads, vec![vec![0, 1, 2], vec![3, 4]]);
+
+    let squads = group_into_squads(&positions, 2, 2);
+    assert_eq!(squads, vec![vec![0, 1], vec![2], vec![3, 4]]);
+}
+
+#[test]
+fn test_flanking_routes_take_different_corridors() {
+    use crate::util::Array2D;
+
+    let maze = Maze {
+        grid: Array2D::from(
+            "\
+            #########\n\
+            #.......#\n\
+            #.#####.#\n\
+            #.#####.#\n\
+            #.#####.#\n\
+            #.......#\n\
+            #########\n"
+                .to_string(),
+        ),
+    };
+
+    let starts = [(1, 3), (1, 4)];
+    let mut reservations = ReservationTable::new(32).without_holding_goals();
+    let routes = plan_flanking_routes(&maze, &mut reservations, &starts, (7, 3), 8);
+    let routes: Vec<Vec<Coord>> = routes.into_iter().map(Option::unwrap).collect();
+
+    let uses_row = |route: &Vec<Coord>, row: usize| route.iter().any(|&(_, y)| y == row);
+    let (top, bottom) = (1, 5);
+
+    // one goes over the top, the other along the bottom
+    assert!(uses_row(&routes[0], top) != uses_row(&routes[0], bottom));
+    assert!(uses_row(&routes[1], top) != uses_row(&routes[1], bottom));
+    assert_ne!(uses_row(&routes[0], top), uses_row(&routes[1], top));
+}
+
+#[test]
+fn test_lone_enemy_target() {
+    use crate::ai::{InfluenceConfig, InfluenceSources};
+    use crate::util::Array2D;
+
+    let maze = Maze {
+        grid: Array2D::from(
+            "\
+            ###########\n\
+            #.........#\n\
+            #.#######.#\n\
+            #.........#\n\
+            ###########\n"
+                .to_string(),
+        ),
+    };
+    let mut maps = InfluenceMaps::new(maze.width, maze.height, InfluenceConfig::default());
+    let config = SquadConfig::default();
+
+    // the player walks right along the top corridor
+    for x in 1..=3 {
+        let sources = InfluenceSources {
+            player: Some((x, 1)),
+            ..Default::default()
+        };
+        maps.update(&maze, &sources);
+    }
+    let player = (3, 1);
+
+    assert_eq!(
+        lone_enemy_target(&maps, &maze, &config, (4, 1), player),
+        player
+    );
+
+    // in the line of fire, out of it into the bottom corridor
+    let cover = lone_enemy_target(&maps, &maze, &config, (9, 1), player);
+    assert!(maps.danger_at(cover) < config.cover_danger);
+
+    // out of sight, waiting for the player to come along
+    let ambush = lone_enemy_target(&maps, &maze, &config, (5, 3), player);
+    assert_eq!(
+        ambush,
+        maps.ambush_cell(&maze, config.ambush_lookahead, config.ambush_radius)
+            .unwrap()
+    );
+}
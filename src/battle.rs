@@ -32,10 +32,25 @@ impl Bullets {
     }
 }
 
+pub use events::*;
+mod events {
+    use bevy::prelude::*;
+
+    /// sent when an enemy is killed by the player or by a bullet
+    pub struct EnemyKilled {
+        pub position: Vec2,
+    }
+
+    /// sent every frame the player is touching something that hurts
+    pub struct PlayerHit;
+}
+
 pub struct BattlePlugin;
 impl Plugin for BattlePlugin {
     fn build(&self, app: &mut App) {
         app
+            .add_event::<EnemyKilled>()
+            .add_event::<PlayerHit>()
             .insert_resource(
                 FireRateState::new(Duration::from_millis(300))
             )
@@ -112,27 +127,32 @@ fn fire_system(
         let player_pos = q.single().translation;
         let mouse_pos = mouse_event.mouse_pos - (Vec2::from(maze.screen_dimensions) / 2.);
         let dir = ((mouse_pos) - player_pos.truncate()).normalize();
-        let speed = 50.;
-
-        let side_size = maze.square_block_side_length / 4.;
-
-        let sprite = Sprite {
-            custom_size: Some(Vec2::new(side_size, side_size)),
-            ..Sprite::from(maze.square_sprite(Color::LIME_GREEN))
-        };
-
-        let bullet = cmd
-            .spawn_bundle(SpriteBundle {
-                sprite,
-                ..Default::default()
-            })
-            .insert(Bullet)
-            .insert(Velocity {
-                velocity: dir * speed,
-                ..Default::default()
-            })
-            .insert(Transform::from_xyz(player_pos.x, player_pos.y, 0.))
-            .insert(movement::Collider::Bullet)
-            .id();
+
+        spawn_bullet(&mut cmd, &maze, player_pos.truncate(), dir);
     }
 }
+
+/// Fires a bullet from the given position in the given (normalized) direction
+pub fn spawn_bullet(cmd: &mut Commands, maze: &MazeResource, from: Vec2, dir: Vec2) -> Entity {
+    let speed = 50.;
+
+    let side_size = maze.square_block_side_length / 4.;
+
+    let sprite = Sprite {
+        custom_size: Some(Vec2::new(side_size, side_size)),
+        ..Sprite::from(maze.square_sprite(Color::LIME_GREEN))
+    };
+
+    cmd.spawn_bundle(SpriteBundle {
+        sprite,
+        ..Default::default()
+    })
+    .insert(Bullet)
+    .insert(Velocity {
+        velocity: dir * speed,
+        ..Default::default()
+    })
+    .insert(Transform::from_xyz(from.x, from.y, 0.))
+    .insert(movement::Collider::Bullet)
+    .id()
+}
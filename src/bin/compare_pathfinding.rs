@@ -0,0 +1,35 @@
+//! Runs every path-finding backend over the mazes in a directory and prints the results, no window needed.
+//!
+//! cargo run --bin compare_pathfinding -- [maze directory] [--markdown] [--out file]
+
+use ai::util::file_io;
+use ai::util::pathfinding::{compare_backends_in_dir, to_csv, to_markdown};
+use anyhow::*;
+
+fn main() -> Result<()> {
+    let mut dir = "saves".to_string();
+    let mut markdown = false;
+    let mut out = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--markdown" => markdown = true,
+            "--out" => out = Some(args.next().context("--out needs a file")?),
+            _ => dir = arg,
+        }
+    }
+
+    let rows = compare_backends_in_dir(&dir)?;
+    let table = if markdown {
+        to_markdown(&rows)
+    } else {
+        to_csv(&rows)
+    };
+
+    match out {
+        Some(path) => file_io::write_to_path(&path, table.as_bytes())?,
+        None => print!("{}", table),
+    }
+    Ok(())
+}
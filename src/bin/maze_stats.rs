@@ -0,0 +1,64 @@
+//! Prints the stats of every maze in a directory, from the easiest to the hardest.
+//!
+//! cargo run --bin maze_stats -- [maze directory]
+
+use ai::maze::Maze;
+use anyhow::*;
+
+fn main() -> Result<()> {
+    let dir = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "saves".to_string());
+
+    let mut paths = std::fs::read_dir(&dir)
+        .with_context(|| format!("couldn't read maze directory {}", dir))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.retain(|path| path.is_file());
+
+    let mut levels = Vec::new();
+    for path in paths {
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let (maze, issues) = match Maze::load_validated(&path.to_string_lossy()) {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                eprintln!("skipping {}: {:?}", name, err);
+                continue;
+            }
+        };
+        if let Some(error) = issues.iter().find(|issue| issue.is_error()) {
+            eprintln!("skipping {}: {}", name, error);
+            continue;
+        }
+        levels.push((name, maze.stats()));
+    }
+    levels.sort_by(|(_, a), (_, b)| a.difficulty.total_cmp(&b.difficulty));
+
+    println!("| maze | difficulty | free cells | dead ends | junctions | branching | loops | largest open area | enemy distances |");
+    println!("| --- | --- | --- | --- | --- | --- | --- | --- | --- |");
+    for (name, stats) in &levels {
+        let distances: Vec<String> = stats
+            .enemy_distances
+            .iter()
+            .map(|(_, distance)| distance.map_or_else(|| "-".to_string(), |d| d.to_string()))
+            .collect();
+
+        println!(
+            "| {} | {:.3} | {} | {} | {} | {:.2} | {} | {} | {} |",
+            name,
+            stats.difficulty,
+            stats.free_cells,
+            stats.dead_ends,
+            stats.junctions,
+            stats.branching_factor,
+            stats.loops,
+            stats.largest_open_area,
+            distances.join(" ")
+        );
+    }
+    Ok(())
+}
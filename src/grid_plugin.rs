@@ -19,6 +19,7 @@ mod components {
     use std::ops::Sub;
 
     #[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Component)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct GridCoord {
         pub x: u32,
         pub y: u32,
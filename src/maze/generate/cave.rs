@@ -0,0 +1,167 @@
+use super::{keep_largest_region, place_spawns};
+use crate::maze::{Coord, Maze, Symbol, SymbolConsts};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Grows open caves out of random noise with a cellular automaton.
+///
+/// The rules count the walls among the 8 cells around each cell, the outside of the maze counts as wall.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaveConfig {
+    /// chance of each cell starting out as a wall
+    pub wall_probability: f64,
+    /// wall counts that turn a free cell into a wall
+    pub birth: Vec<usize>,
+    /// wall counts that keep a wall standing
+    pub survival: Vec<usize>,
+    pub smoothing_passes: usize,
+    pub enemies: usize,
+}
+
+impl Default for CaveConfig {
+    fn default() -> Self {
+        Self {
+            wall_probability: 0.45,
+            birth: vec![5, 6, 7, 8],
+            survival: vec![4, 5, 6, 7, 8],
+            smoothing_passes: 5,
+            enemies: 3,
+        }
+    }
+}
+
+impl CaveConfig {
+    /// Generates a cave surrounded by walls. Only the largest open region is kept so every free cell can be
+    ///  reached from the player spawn.
+    pub fn generate(&self, width: usize, height: usize, seed: u64) -> Maze {
+        assert!(
+            width >= 3 && height >= 3,
+            "a cave needs to be at least 3x3 to fit a cell, got {}x{}",
+            width,
+            height
+        );
+
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let is_border = |(x, y): Coord| x == 0 || y == 0 || x == width - 1 || y == height - 1;
+
+        let mut maze = Maze::new_empty(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                if is_border((x, y)) || rng.gen_bool(self.wall_probability) {
+                    maze.set((x, y), Symbol::BLOCKED);
+                }
+            }
+        }
+
+        for _ in 0..self.smoothing_passes {
+            let mut next = Maze::new_empty(width, height);
+            for (coord, _) in maze.iter_rows_first_enumerated() {
+                let walls = surrounding_walls(&maze, coord);
+                let wall = if is_border(coord) {
+                    true
+                } else if maze.is_walkable(coord) {
+                    self.birth.contains(&walls)
+                } else {
+                    self.survival.contains(&walls)
+                };
+
+                if wall {
+                    next.set(coord, Symbol::BLOCKED);
+                }
+            }
+            maze = next;
+        }
+
+        keep_largest_region(&mut maze);
+        if maze.iter_data().all(|&symbol| symbol == Symbol::BLOCKED) {
+            // all wall, leave some room for the player
+            maze.set((width / 2, height / 2), Symbol::FREE);
+        }
+
+        place_spawns(&mut maze, self.enemies, &mut rng);
+        maze
+    }
+}
+
+fn surrounding_walls(maze: &Maze, coord: Coord) -> usize {
+    // outside of the maze is wall as well
+    let free = maze
+        .neighbours_8(coord)
+        .filter(|&neighbour| maze.is_walkable(neighbour))
+        .count();
+    8 - free
+}
+
+#[test]
+fn test_caves_are_connected() {
+    use crate::util::pathfinding::distances_to;
+
+    let config = CaveConfig::default();
+    for seed in 0..10 {
+        let maze = config.generate(40, 25, seed);
+        assert_eq!(maze, config.generate(40, 25, seed));
+
+        let player = maze.player_spawn_coord().unwrap();
+        assert_eq!(maze.enemy_spawn_coords().len(), config.enemies);
+
+        let distances = distances_to(&maze, player);
+        for (coord, _) in maze.iter_rows_first_enumerated() {
+            let border = coord.0 == 0 || coord.1 == 0 || coord.0 == 39 || coord.1 == 24;
+            assert!(!(border && maze.is_walkable(coord)));
+            assert_eq!(
+                maze.is_walkable(coord),
+                distances.get(coord).is_some(),
+                "{:?}\n{}",
+                coord,
+                maze.grid.to_string()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_cave_rules() {
+    // walls that never survive leave one big open room
+    let config = CaveConfig {
+        survival: Vec::new(),
+        birth: Vec::new(),
+        smoothing_passes: 1,
+        enemies: 0,
+        ..Default::default()
+    };
+    let maze = config.generate(6, 4, 3);
+    assert_eq!(
+        maze.grid
+            .to_string()
+            .replace(Symbol::PLAYER_SPAWN, &Symbol::FREE.to_string()),
+        "\
+        ######\n\
+        #....#\n\
+        #....#\n\
+        ######\n"
+    );
+
+    // pockets split off from the largest cave are filled
+    use crate::util::Array2D;
+    let mut maze = Maze {
+        grid: Array2D::from(
+            "\
+            #######\n\
+            #..#..#\n\
+            #..#..#\n\
+            ####..#\n\
+            #######\n"
+                .to_string(),
+        ),
+    };
+    keep_largest_region(&mut maze);
+    assert_eq!(
+        maze.grid.to_string(),
+        "\
+        #######\n\
+        ####..#\n\
+        ####..#\n\
+        ####..#\n\
+        #######\n"
+    );
+}
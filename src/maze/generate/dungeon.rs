@@ -0,0 +1,322 @@
+use crate::maze::{Coord, Maze, Symbol, SymbolConsts};
+use crate::util::pathfinding::distances_to;
+use crate::util::Array2D;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::cmp::Reverse;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CorridorStyle {
+    /// a single straight line where the rooms line up, L-shaped where they don't
+    Straight,
+    LShaped,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RoomTag {
+    Empty,
+    Player,
+    Enemy,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Room {
+    /// bottom left corner
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub tag: RoomTag,
+}
+
+impl Room {
+    pub fn center(&self) -> Coord {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    pub fn contains(&self, (x, y): Coord) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+
+    fn cells(&self) -> Vec<Coord> {
+        (self.y..self.y + self.height)
+            .flat_map(|y| (self.x..self.x + self.width).map(move |x| (x, y)))
+            .collect()
+    }
+}
+
+/// Rooms and corridors, laid out by splitting the level in two over and over (binary space partitioning)
+///  and putting a room in every part.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DungeonConfig {
+    /// smallest width and height of a room
+    pub min_room_size: usize,
+    /// largest width and height of a room
+    pub max_room_size: usize,
+    pub corridor_style: CorridorStyle,
+    pub enemies: usize,
+}
+
+impl Default for DungeonConfig {
+    fn default() -> Self {
+        Self {
+            min_room_size: 3,
+            max_room_size: 8,
+            corridor_style: CorridorStyle::LShaped,
+            enemies: 3,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Dungeon {
+    pub maze: Maze,
+    pub rooms: Vec<Room>,
+}
+
+impl DungeonConfig {
+    /// The player spawns in the middle of a random room, the enemies spread over the rooms furthest from it.
+    pub fn generate(&self, width: usize, height: usize, seed: u64) -> Dungeon {
+        assert!(
+            self.min_room_size > 0 && self.min_room_size <= self.max_room_size,
+            "invalid room size limits {}..={}",
+            self.min_room_size,
+            self.max_room_size
+        );
+        assert!(
+            width >= self.min_room_size + 2 && height >= self.min_room_size + 2,
+            "a {}x{} dungeon can't fit a room",
+            width,
+            height
+        );
+
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut maze = Maze {
+            grid: Array2D::new(width, height, Symbol::BLOCKED),
+        };
+        let mut rooms = Vec::new();
+        self.split(&mut maze, &mut rooms, (0, 0, width, height), &mut rng);
+
+        self.place_spawns(&mut maze, &mut rooms, &mut rng);
+        Dungeon { maze, rooms }
+    }
+
+    /// Splits the area until the parts are small enough for a room, carves the rooms and connects the two
+    ///  halves of every split. Returns the indices of the rooms carved in the area.
+    fn split(
+        &self,
+        maze: &mut Maze,
+        rooms: &mut Vec<Room>,
+        (x, y, width, height): (usize, usize, usize, usize),
+        rng: &mut impl Rng,
+    ) -> Vec<usize> {
+        // every part keeps a wall around its room
+        let min_part = self.min_room_size + 2;
+        let max_part = self.max_room_size + 2;
+        let can_split_x = width >= 2 * min_part;
+        let can_split_y = height >= 2 * min_part;
+
+        let split_x = match (can_split_x, can_split_y) {
+            _ if width <= max_part && height <= max_part => None,
+            (true, true) => Some(width >= height),
+            (true, false) => Some(true),
+            (false, true) => Some(false),
+            (false, false) => None,
+        };
+
+        let (first, second) = match split_x {
+            None => {
+                let room = self.carve_room(maze, (x, y, width, height), rng);
+                rooms.push(room);
+                return vec![rooms.len() - 1];
+            }
+            Some(true) => {
+                let at = rng.gen_range(min_part..=width - min_part);
+                (
+                    self.split(maze, rooms, (x, y, at, height), rng),
+                    self.split(maze, rooms, (x + at, y, width - at, height), rng),
+                )
+            }
+            Some(false) => {
+                let at = rng.gen_range(min_part..=height - min_part);
+                (
+                    self.split(maze, rooms, (x, y, width, at), rng),
+                    self.split(maze, rooms, (x, y + at, width, height - at), rng),
+                )
+            }
+        };
+
+        // the closest rooms of both halves
+        let distance = |a: &Room, b: &Room| {
+            let (a, b) = (a.center(), b.center());
+            a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+        };
+        let (a, b) = first
+            .iter()
+            .flat_map(|&a| second.iter().map(move |&b| (a, b)))
+            .min_by_key(|&(a, b)| distance(&rooms[a], &rooms[b]))
+            .unwrap();
+        self.carve_corridor(maze, &rooms[a], &rooms[b], rng);
+
+        first.into_iter().chain(second).collect()
+    }
+
+    fn carve_room(
+        &self,
+        maze: &mut Maze,
+        (x, y, width, height): (usize, usize, usize, usize),
+        rng: &mut impl Rng,
+    ) -> Room {
+        let room_width = rng.gen_range(self.min_room_size..=self.max_room_size.min(width - 2));
+        let room_height = rng.gen_range(self.min_room_size..=self.max_room_size.min(height - 2));
+        let room = Room {
+            x: x + rng.gen_range(1..=width - 1 - room_width),
+            y: y + rng.gen_range(1..=height - 1 - room_height),
+            width: room_width,
+            height: room_height,
+            tag: RoomTag::Empty,
+        };
+
+        for cell in room.cells() {
+            maze.set(cell, Symbol::FREE);
+        }
+        room
+    }
+
+    fn carve_corridor(&self, maze: &mut Maze, a: &Room, b: &Room, rng: &mut impl Rng) {
+        let (from, to) = (a.center(), b.center());
+
+        if self.corridor_style == CorridorStyle::Straight {
+            // a column or row both rooms share
+            let xs = a.x.max(b.x)..(a.x + a.width).min(b.x + b.width);
+            let ys = a.y.max(b.y)..(a.y + a.height).min(b.y + b.height);
+            if !xs.is_empty() {
+                let x = rng.gen_range(xs);
+                carve_line(maze, (x, from.1), (x, to.1));
+                return;
+            }
+            if !ys.is_empty() {
+                let y = rng.gen_range(ys);
+                carve_line(maze, (from.0, y), (to.0, y));
+                return;
+            }
+        }
+
+        let corner = if rng.gen_bool(0.5) {
+            (to.0, from.1)
+        } else {
+            (from.0, to.1)
+        };
+        carve_line(maze, from, corner);
+        carve_line(maze, corner, to);
+    }
+
+    fn place_spawns(&self, maze: &mut Maze, rooms: &mut [Room], rng: &mut impl Rng) {
+        let player_room = rng.gen_range(0..rooms.len());
+        let player = rooms[player_room].center();
+        rooms[player_room].tag = RoomTag::Player;
+        maze.set(player, Symbol::PLAYER_SPAWN);
+        if self.enemies == 0 {
+            return;
+        }
+
+        // the furthest half of the other rooms, walking distance from the player
+        let distances = distances_to(maze, player);
+        let mut others: Vec<usize> = (0..rooms.len()).filter(|&i| i != player_room).collect();
+        others.sort_by_key(|&i| Reverse(distances.get(rooms[i].center()).unwrap_or(0)));
+        others.truncate(others.len().div_ceil(2));
+        if others.is_empty() {
+            others.push(player_room);
+        }
+
+        let mut free_cells: Vec<Vec<Coord>> = others
+            .iter()
+            .map(|&i| {
+                let mut cells = rooms[i].cells();
+                cells.retain(|&cell| cell != player);
+                cells.shuffle(rng);
+                cells
+            })
+            .collect();
+
+        // one enemy per room in turn
+        let mut placed = 0;
+        while placed < self.enemies && free_cells.iter().any(|cells| !cells.is_empty()) {
+            for (&room, cells) in others.iter().zip(&mut free_cells) {
+                if placed == self.enemies {
+                    break;
+                }
+                if let Some(cell) = cells.pop() {
+                    maze.set(cell, Symbol::ENEMY_SPAWN);
+                    if rooms[room].tag == RoomTag::Empty {
+                        rooms[room].tag = RoomTag::Enemy;
+                    }
+                    placed += 1;
+                }
+            }
+        }
+    }
+}
+
+/// carves a horizontal or vertical line
+fn carve_line(maze: &mut Maze, from: Coord, to: Coord) {
+    for x in from.0.min(to.0)..=from.0.max(to.0) {
+        for y in from.1.min(to.1)..=from.1.max(to.1) {
+            maze.set((x, y), Symbol::FREE);
+        }
+    }
+}
+
+#[test]
+fn test_dungeon_rooms_and_spawns() {
+    for corridor_style in [CorridorStyle::Straight, CorridorStyle::LShaped] {
+        let config = DungeonConfig {
+            corridor_style,
+            ..Default::default()
+        };
+
+        for seed in 0..10 {
+            let Dungeon { maze, rooms } = config.generate(50, 30, seed);
+            assert!(rooms.len() > 2);
+            for room in &rooms {
+                assert!((config.min_room_size..=config.max_room_size).contains(&room.width));
+                assert!((config.min_room_size..=config.max_room_size).contains(&room.height));
+            }
+
+            // all connected
+            let player = maze.player_spawn_coord().unwrap();
+            let distances = distances_to(&maze, player);
+            for (coord, _) in maze.iter_rows_first_enumerated() {
+                assert_eq!(maze.is_walkable(coord), distances.get(coord).is_some());
+            }
+
+            let player_rooms: Vec<&Room> = rooms
+                .iter()
+                .filter(|room| room.tag == RoomTag::Player)
+                .collect();
+            assert_eq!(player_rooms.len(), 1);
+            assert!(player_rooms[0].contains(player));
+
+            let enemies = maze.enemy_spawn_coords();
+            assert_eq!(enemies.len(), config.enemies);
+            for enemy in enemies {
+                let room = rooms.iter().find(|room| room.contains(enemy)).unwrap();
+                assert_eq!(room.tag, RoomTag::Enemy);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_save_dungeon() {
+    let dungeon = DungeonConfig::default().generate(40, 20, 5);
+
+    let path = std::env::temp_dir().join("ai_dungeon_test.txt");
+    let path = path.to_str().unwrap();
+    dungeon.maze.save_to_file(path);
+    let loaded = Maze::load_from_file(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(loaded, dungeon.maze);
+}
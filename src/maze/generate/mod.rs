@@ -0,0 +1,467 @@
+//! Procedural maze generation. Generation is seeded, the same seed always gives the same maze.
+
+mod cave;
+pub use cave::*;
+
+mod dungeon;
+pub use dungeon::*;
+
+mod wfc;
+pub use wfc::*;
+
+use crate::maze::{Coord, Maze, Symbol, SymbolConsts};
+use crate::util::pathfinding::distances_to;
+use crate::util::{Array2D, Direction};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::{BTreeMap, HashMap};
+
+/// Perfect maze algorithms: exactly one way between any two cells.
+///
+/// They carve passages between cells on the odd coordinates of the maze, the tiles in between stay walls
+///  unless a passage goes through them.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Algorithm {
+    /// depth first, long winding corridors with few dead ends
+    RecursiveBacktracker,
+    /// grows outwards from the start, lots of short dead ends
+    Prim,
+    /// joins random walls between unconnected cells
+    Kruskal,
+    /// loop-erased random walks, an unbiased pick among all possible mazes
+    Wilson,
+    /// one row at a time
+    Eller,
+}
+
+impl Algorithm {
+    pub const ALL: [Self; 5] = [
+        Self::RecursiveBacktracker,
+        Self::Prim,
+        Self::Kruskal,
+        Self::Wilson,
+        Self::Eller,
+    ];
+
+    /// Generates a maze with a player spawn and up to `enemies` enemy spawns, as far from the player as there
+    ///  is room for. The same seed always gives the same maze.
+    ///
+    /// With an even width or height the last column or row is all wall.
+    pub fn generate(self, width: usize, height: usize, enemies: usize, seed: u64) -> Maze {
+        assert!(
+            width >= 3 && height >= 3,
+            "a maze needs to be at least 3x3 to fit a cell, got {}x{}",
+            width,
+            height
+        );
+
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut cells = Cells::new(width, height);
+        match self {
+            Self::RecursiveBacktracker => recursive_backtracker(&mut cells, &mut rng),
+            Self::Prim => prim(&mut cells, &mut rng),
+            Self::Kruskal => kruskal(&mut cells, &mut rng),
+            Self::Wilson => wilson(&mut cells, &mut rng),
+            Self::Eller => eller(&mut cells, &mut rng),
+        }
+
+        let mut maze = cells.maze;
+        place_spawns(&mut maze, enemies, &mut rng);
+        maze
+    }
+}
+
+// the grid of cells the passages are carved between
+struct Cells {
+    width: usize,
+    height: usize,
+    maze: Maze,
+}
+
+impl Cells {
+    fn new(maze_width: usize, maze_height: usize) -> Self {
+        Self {
+            width: (maze_width - 1) / 2,
+            height: (maze_height - 1) / 2,
+            maze: Maze {
+                grid: Array2D::new(maze_width, maze_height, Symbol::BLOCKED),
+            },
+        }
+    }
+
+    fn tile((x, y): Coord) -> Coord {
+        (2 * x + 1, 2 * y + 1)
+    }
+
+    fn all(&self) -> Vec<Coord> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .collect()
+    }
+
+    fn random(&self, rng: &mut impl Rng) -> Coord {
+        (rng.gen_range(0..self.width), rng.gen_range(0..self.height))
+    }
+
+    fn neighbours(&self, cell: Coord) -> Vec<Coord> {
+        Direction::ALL
+            .iter()
+            .filter_map(|dir| dir.step(cell, (self.width, self.height)))
+            .collect()
+    }
+
+    fn is_carved(&self, cell: Coord) -> bool {
+        self.maze.is_walkable(Self::tile(cell))
+    }
+
+    fn carve(&mut self, cell: Coord) {
+        self.maze.set(Self::tile(cell), Symbol::FREE);
+    }
+
+    /// carves both cells and the wall between them
+    fn connect(&mut self, a: Coord, b: Coord) {
+        let (ta, tb) = (Self::tile(a), Self::tile(b));
+        self.carve(a);
+        self.carve(b);
+        self.maze
+            .set(((ta.0 + tb.0) / 2, (ta.1 + tb.1) / 2), Symbol::FREE);
+    }
+}
+
+fn recursive_backtracker(cells: &mut Cells, rng: &mut impl Rng) {
+    let start = cells.random(rng);
+    cells.carve(start);
+
+    let mut stack = vec![start];
+    while let Some(&cell) = stack.last() {
+        let unvisited: Vec<Coord> = cells
+            .neighbours(cell)
+            .into_iter()
+            .filter(|&neighbour| !cells.is_carved(neighbour))
+            .collect();
+
+        match unvisited.choose(rng) {
+            Some(&next) => {
+                cells.connect(cell, next);
+                stack.push(next);
+            }
+            None => {
+                stack.pop();
+            }
+        }
+    }
+}
+
+fn prim(cells: &mut Cells, rng: &mut impl Rng) {
+    let start = cells.random(rng);
+    cells.carve(start);
+
+    // cells next to the maze so far, may hold duplicates and cells carved since they were added
+    let mut frontier = cells.neighbours(start);
+    while !frontier.is_empty() {
+        let cell = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+        if cells.is_carved(cell) {
+            continue;
+        }
+
+        let carved: Vec<Coord> = cells
+            .neighbours(cell)
+            .into_iter()
+            .filter(|&neighbour| cells.is_carved(neighbour))
+            .collect();
+        let &into = carved.choose(rng).unwrap();
+        cells.connect(cell, into);
+
+        for neighbour in cells.neighbours(cell) {
+            if !cells.is_carved(neighbour) {
+                frontier.push(neighbour);
+            }
+        }
+    }
+}
+
+fn kruskal(cells: &mut Cells, rng: &mut impl Rng) {
+    let all = cells.all();
+    let (width, height) = (cells.width, cells.height);
+    let index = |(x, y): Coord| y * width + x;
+
+    let mut walls: Vec<(Coord, Coord)> = all
+        .iter()
+        .flat_map(|&cell| {
+            [Direction::Right, Direction::Up]
+                .into_iter()
+                .filter_map(move |dir| dir.step(cell, (width, height)))
+                .map(move |neighbour| (cell, neighbour))
+        })
+        .collect();
+    walls.shuffle(rng);
+
+    // union-find over the cells, every cell starts out as its own set
+    let mut parents: Vec<usize> = (0..all.len()).collect();
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    for &cell in &all {
+        cells.carve(cell);
+    }
+    for (a, b) in walls {
+        let (root_a, root_b) = (root(&mut parents, index(a)), root(&mut parents, index(b)));
+        if root_a != root_b {
+            parents[root_a] = root_b;
+            cells.connect(a, b);
+        }
+    }
+}
+
+fn wilson(cells: &mut Cells, rng: &mut impl Rng) {
+    let start = cells.random(rng);
+    cells.carve(start);
+
+    for cell in cells.all() {
+        // random walk until it hits the maze, only the last step out of every cell is kept which erases the loops
+        let mut next_steps = HashMap::new();
+        let mut current = cell;
+        while !cells.is_carved(current) {
+            let &next = cells.neighbours(current).choose(rng).unwrap();
+            next_steps.insert(current, next);
+            current = next;
+        }
+
+        let mut path = vec![cell];
+        while let Some(&next) = path.last().and_then(|last| next_steps.get(last)) {
+            path.push(next);
+            if cells.is_carved(next) {
+                break;
+            }
+        }
+        for step in path.windows(2) {
+            cells.connect(step[0], step[1]);
+        }
+    }
+}
+
+fn eller(cells: &mut Cells, rng: &mut impl Rng) {
+    // the set of every cell in the current row, cells in the same set are already connected
+    let mut sets: Vec<usize> = (0..cells.width).collect();
+    let mut next_set = cells.width;
+
+    for y in 0..cells.height {
+        let last_row = y + 1 == cells.height;
+        for x in 0..cells.width {
+            cells.carve((x, y));
+        }
+
+        // join neighbours at random, and everything left in the last row so the whole maze is connected
+        for x in 1..cells.width {
+            if sets[x - 1] != sets[x] && (last_row || rng.gen_bool(0.5)) {
+                cells.connect((x - 1, y), (x, y));
+                let (from, to) = (sets[x], sets[x - 1]);
+                for set in sets.iter_mut().filter(|set| **set == from) {
+                    *set = to;
+                }
+            }
+        }
+        if last_row {
+            break;
+        }
+
+        let mut by_set: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (x, &set) in sets.iter().enumerate() {
+            by_set.entry(set).or_default().push(x);
+        }
+
+        // every set carries on into the next row at least once, or it would be cut off
+        let mut below = vec![None; cells.width];
+        for (set, mut xs) in by_set {
+            xs.shuffle(rng);
+            let down = rng.gen_range(1..=xs.len());
+            for &x in &xs[..down] {
+                cells.connect((x, y), (x, y + 1));
+                below[x] = Some(set);
+            }
+        }
+
+        sets = below
+            .into_iter()
+            .map(|set| {
+                set.unwrap_or_else(|| {
+                    next_set += 1;
+                    next_set
+                })
+            })
+            .collect();
+    }
+}
+
+/// the player on a random cell, the enemies in the half of the maze furthest away from it
+fn place_spawns(maze: &mut Maze, enemies: usize, rng: &mut impl Rng) {
+    let free: Vec<Coord> = maze
+        .iter_rows_first_enumerated()
+        .filter(|&(_, &symbol)| symbol == Symbol::FREE)
+        .map(|(coord, _)| coord)
+        .collect();
+    let player = *free.choose(rng).unwrap();
+    maze.set(player, Symbol::PLAYER_SPAWN);
+
+    let distances = distances_to(maze, player);
+    let distance = |cell: Coord| distances.get(cell).unwrap_or(0);
+    let furthest = free.iter().map(|&cell| distance(cell)).max().unwrap_or(0);
+
+    let mut candidates: Vec<Coord> = free
+        .iter()
+        .copied()
+        .filter(|&cell| cell != player && distance(cell) * 2 >= furthest)
+        .collect();
+    if candidates.len() < enemies {
+        candidates = free.into_iter().filter(|&cell| cell != player).collect();
+    }
+
+    candidates.shuffle(rng);
+    for &cell in candidates.iter().take(enemies) {
+        maze.set(cell, Symbol::ENEMY_SPAWN);
+    }
+}
+
+/// fills every open region but the largest one
+fn keep_largest_region(maze: &mut Maze) {
+    let regions = maze.label_components(|&symbol| symbol != Symbol::BLOCKED);
+    let largest = regions.largest();
+    for (coord, &label) in regions.labels.iter_rows_first_enumerated() {
+        if label != 0 && Some(label) != largest {
+            maze.set(coord, Symbol::BLOCKED);
+        }
+    }
+}
+
+#[test]
+fn test_generated_mazes_are_perfect() {
+    use crate::util::pathfinding::walkable_neighbours;
+
+    for algorithm in Algorithm::ALL {
+        for (width, height) in [(3, 3), (4, 6), (21, 15)] {
+            let maze = algorithm.generate(width, height, 3, 7);
+            assert_eq!(maze.width, width);
+            assert_eq!(maze.height, height);
+
+            let player = maze.player_spawn_coord().unwrap();
+            let walkable: Vec<Coord> = maze
+                .iter_rows_first_enumerated()
+                .map(|(coord, _)| coord)
+                .filter(|&coord| maze.is_walkable(coord))
+                .collect();
+            assert_eq!(
+                maze.enemy_spawn_coords().len(),
+                3.min(walkable.len() - 1),
+                "{:?}",
+                algorithm
+            );
+
+            // all connected, and without loops
+            let distances = distances_to(&maze, player);
+            assert!(
+                walkable.iter().all(|&cell| distances.get(cell).is_some()),
+                "{:?}\n{}",
+                algorithm,
+                maze.to_string()
+            );
+            let edges: usize = walkable
+                .iter()
+                .map(|&cell| walkable_neighbours(&maze, cell).count())
+                .sum::<usize>()
+                / 2;
+            assert_eq!(
+                edges,
+                walkable.len() - 1,
+                "{:?}\n{}",
+                algorithm,
+                maze.to_string()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_generation_is_deterministic() {
+    for algorithm in Algorithm::ALL {
+        assert_eq!(
+            algorithm.generate(15, 11, 2, 42),
+            algorithm.generate(15, 11, 2, 42)
+        );
+    }
+
+    assert_ne!(
+        Algorithm::Wilson.generate(15, 11, 2, 1),
+        Algorithm::Wilson.generate(15, 11, 2, 2)
+    );
+}
+
+#[test]
+fn test_generator_snapshots() {
+    let snapshot = |algorithm: Algorithm| algorithm.generate(11, 7, 2, 1).grid.to_string();
+
+    assert_eq!(
+        snapshot(Algorithm::RecursiveBacktracker),
+        "\
+            ###########\n\
+            #..E#.#...#\n\
+            #.###.#.#.#\n\
+            #.#.P.#.#.#\n\
+            #.#.#####E#\n\
+            #.........#\n\
+            ###########\n"
+    );
+
+    assert_eq!(
+        snapshot(Algorithm::Prim),
+        "\
+            ###########\n\
+            #E#.#.....#\n\
+            #.#.#P#.###\n\
+            #.....#.#.#\n\
+            #.#.#.#.#E#\n\
+            #.#.#.#...#\n\
+            ###########\n"
+    );
+
+    assert_eq!(
+        snapshot(Algorithm::Kruskal),
+        "\
+            ###########\n\
+            #.....#..E#\n\
+            ###E###.###\n\
+            #.........#\n\
+            #######.#.#\n\
+            #.....P.#.#\n\
+            ###########\n"
+    );
+
+    assert_eq!(
+        snapshot(Algorithm::Wilson),
+        "\
+            ###########\n\
+            #.#...#P#.#\n\
+            #.#.#E#.#.#\n\
+            #.E.#.#...#\n\
+            #.#.#.#.#.#\n\
+            #.#.#...#.#\n\
+            ###########\n"
+    );
+
+    assert_eq!(
+        snapshot(Algorithm::Eller),
+        "\
+            ###########\n\
+            #.....#...#\n\
+            #E#.###.###\n\
+            #.#.P...#.#\n\
+            #.#.#####.#\n\
+            #.#......E#\n\
+            ###########\n"
+    );
+}
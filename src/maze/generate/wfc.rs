@@ -0,0 +1,358 @@
+use super::{keep_largest_region, place_spawns};
+use crate::maze::{Maze, Symbol, SymbolConsts};
+use crate::util::Direction;
+use anyhow::*;
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashMap;
+
+// give up after undoing this many choices
+const MAX_BACKTRACKS: usize = 10_000;
+
+/// Wave Function Collapse, overlapping model: learns every N×N window of some example mazes and fills new
+///  mazes so that each N×N window of them shows up somewhere in the examples.
+///
+/// Only the layout is learned, spawns in the examples count as free cells and new ones are placed in the
+///  generated maze.
+#[derive(Debug, Clone)]
+pub struct WaveFunctionCollapse {
+    pub pattern_size: usize,
+    // n×n symbols each, rows first
+    patterns: Vec<Vec<Symbol>>,
+    // how often each pattern shows up in the examples
+    weights: Vec<u32>,
+    // the patterns that may be placed next to each pattern, in the order of `Direction::ALL`
+    compatible: Vec<[Vec<usize>; 4]>,
+}
+
+impl WaveFunctionCollapse {
+    pub fn learn(examples: &[&Maze], pattern_size: usize) -> Result<Self> {
+        ensure!(pattern_size > 0, "patterns need at least one cell");
+
+        let n = pattern_size;
+        let mut patterns = Vec::new();
+        let mut weights = Vec::new();
+        let mut index = HashMap::new();
+        for example in examples {
+            if example.width < n || example.height < n {
+                continue;
+            }
+
+            for y in 0..=example.height - n {
+                for x in 0..=example.width - n {
+                    let pattern: Vec<Symbol> = (0..n * n)
+                        .map(|i| match *example.get((x + i % n, y + i / n)) {
+                            Symbol::BLOCKED => Symbol::BLOCKED,
+                            _ => Symbol::FREE,
+                        })
+                        .collect();
+
+                    let i = *index.entry(pattern.clone()).or_insert_with(|| {
+                        patterns.push(pattern);
+                        weights.push(0);
+                        patterns.len() - 1
+                    });
+                    weights[i] += 1;
+                }
+            }
+        }
+        ensure!(
+            !patterns.is_empty(),
+            "no example is at least {}x{} to learn patterns from",
+            n,
+            n
+        );
+
+        let compatible = patterns
+            .iter()
+            .map(|pattern| {
+                Direction::ALL.map(|dir| {
+                    (0..patterns.len())
+                        .filter(|&other| overlap_agrees(pattern, &patterns[other], n, dir))
+                        .collect()
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            pattern_size,
+            patterns,
+            weights,
+            compatible,
+        })
+    }
+
+    pub fn learn_from_files(paths: &[&str], pattern_size: usize) -> Result<Self> {
+        let examples = paths
+            .iter()
+            .map(|&path| Maze::load_from_file(path))
+            .collect::<Result<Vec<Maze>>>()?;
+
+        Self::learn(&examples.iter().collect::<Vec<_>>(), pattern_size)
+    }
+
+    pub fn pattern_count(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// A new maze in the style of the examples. Only its largest open region is kept, the player spawn and
+    ///  `enemies` enemy spawns are placed in it.
+    pub fn generate(&self, width: usize, height: usize, enemies: usize, seed: u64) -> Result<Maze> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut maze = self.synthesize(width, height, &mut rng)?;
+
+        keep_largest_region(&mut maze);
+        ensure!(
+            maze.iter_data().any(|&symbol| symbol == Symbol::FREE),
+            "the generated maze has no free cells"
+        );
+        place_spawns(&mut maze, enemies, &mut rng);
+        Ok(maze)
+    }
+
+    fn synthesize(&self, width: usize, height: usize, rng: &mut ChaCha8Rng) -> Result<Maze> {
+        let n = self.pattern_size;
+        ensure!(
+            width >= n && height >= n,
+            "a {}x{} maze can't fit a {}x{} pattern",
+            width,
+            height,
+            n,
+            n
+        );
+
+        let mut wave = Wave::new(width - n + 1, height - n + 1, self.patterns.len());
+        // the edges of the maze may already rule some patterns out
+        for position in 0..wave.possible.len() {
+            ensure!(
+                self.propagate(&mut wave, position),
+                "the examples can't be fit into a maze of this size"
+            );
+        }
+
+        // (length of the trail before the choice, position, pattern chosen)
+        let mut choices: Vec<(usize, usize, usize)> = Vec::new();
+        let mut backtracks = 0;
+
+        while let Some(position) = wave.most_constrained(rng) {
+            let options: Vec<usize> = wave.options(position).collect();
+            let weights = options.iter().map(|&pattern| self.weights[pattern]);
+            let pattern = options[WeightedIndex::new(weights)?.sample(rng)];
+
+            choices.push((wave.trail.len(), position, pattern));
+            let mut consistent = options
+                .iter()
+                .filter(|&&other| other != pattern)
+                .all(|&other| wave.ban(position, other))
+                && self.propagate(&mut wave, position);
+
+            // undo the last choice and rule it out, going further back if that doesn't work either
+            while !consistent {
+                let (trail_len, position, pattern) = choices
+                    .pop()
+                    .context("the examples can't be fit into a maze of this size")?;
+                backtracks += 1;
+                ensure!(
+                    backtracks <= MAX_BACKTRACKS,
+                    "gave up after {} backtracks",
+                    MAX_BACKTRACKS
+                );
+
+                wave.undo(trail_len);
+                consistent = wave.ban(position, pattern) && self.propagate(&mut wave, position);
+            }
+        }
+
+        let mut maze = Maze::new_empty(width, height);
+        for position in 0..wave.possible.len() {
+            let pattern = &self.patterns[wave.options(position).next().unwrap()];
+            let (x, y) = (position % wave.width, position / wave.width);
+            for (i, &symbol) in pattern.iter().enumerate() {
+                maze.set((x + i % n, y + i / n), symbol);
+            }
+        }
+        Ok(maze)
+    }
+
+    /// removes the patterns around the position that no longer fit with any of its options, and so on
+    fn propagate(&self, wave: &mut Wave, position: usize) -> bool {
+        let mut stack = vec![position];
+        while let Some(position) = stack.pop() {
+            let coord = (position % wave.width, position / wave.width);
+            for (d, dir) in Direction::ALL.into_iter().enumerate() {
+                let neighbour = match dir.step(coord, (wave.width, wave.height)) {
+                    Some((x, y)) => y * wave.width + x,
+                    None => continue,
+                };
+
+                let mut allowed = vec![false; self.patterns.len()];
+                for pattern in wave.options(position) {
+                    for &other in &self.compatible[pattern][d] {
+                        allowed[other] = true;
+                    }
+                }
+
+                let banned: Vec<usize> = wave
+                    .options(neighbour)
+                    .filter(|&other| !allowed[other])
+                    .collect();
+                if banned.is_empty() {
+                    continue;
+                }
+                for other in banned {
+                    if !wave.ban(neighbour, other) {
+                        return false;
+                    }
+                }
+                stack.push(neighbour);
+            }
+        }
+        true
+    }
+}
+
+/// true if `other`, one step in `dir` from `pattern`, has the same symbols where the two overlap
+fn overlap_agrees(pattern: &[Symbol], other: &[Symbol], n: usize, dir: Direction) -> bool {
+    let (dx, dy) = dir.offset();
+    (0..n * n).all(|i| {
+        let (x, y) = ((i % n) as isize, (i / n) as isize);
+        let (ox, oy) = (x - dx, y - dy);
+        let outside = ox < 0 || oy < 0 || ox >= n as isize || oy >= n as isize;
+        outside || pattern[i] == other[oy as usize * n + ox as usize]
+    })
+}
+
+// the patterns still possible at every position a pattern can be placed
+struct Wave {
+    width: usize,
+    height: usize,
+    possible: Vec<Vec<bool>>,
+    counts: Vec<usize>,
+    // every (position, pattern) banned so far, in order, to undo them when backtracking
+    trail: Vec<(usize, usize)>,
+}
+
+impl Wave {
+    fn new(width: usize, height: usize, patterns: usize) -> Self {
+        Self {
+            width,
+            height,
+            possible: vec![vec![true; patterns]; width * height],
+            counts: vec![patterns; width * height],
+            trail: Vec::new(),
+        }
+    }
+
+    fn options(&self, position: usize) -> impl Iterator<Item = usize> + '_ {
+        self.possible[position]
+            .iter()
+            .enumerate()
+            .filter(|(_, &possible)| possible)
+            .map(|(pattern, _)| pattern)
+    }
+
+    /// false if that was the last option of the position
+    fn ban(&mut self, position: usize, pattern: usize) -> bool {
+        if self.possible[position][pattern] {
+            self.possible[position][pattern] = false;
+            self.counts[position] -= 1;
+            self.trail.push((position, pattern));
+        }
+        self.counts[position] > 0
+    }
+
+    fn undo(&mut self, trail_len: usize) {
+        for (position, pattern) in self.trail.drain(trail_len..) {
+            self.possible[position][pattern] = true;
+            self.counts[position] += 1;
+        }
+    }
+
+    /// a random one of the undecided positions with the fewest options left, None once all are decided
+    fn most_constrained(&self, rng: &mut ChaCha8Rng) -> Option<usize> {
+        let fewest = self.counts.iter().filter(|&&count| count > 1).min()?;
+        let positions: Vec<usize> = (0..self.counts.len())
+            .filter(|&position| self.counts[position] == *fewest)
+            .collect();
+        positions.choose(rng).copied()
+    }
+}
+
+#[test]
+fn test_wave_function_collapse() {
+    use super::Algorithm;
+
+    let example = Algorithm::RecursiveBacktracker.generate(21, 15, 0, 3);
+    let wfc = WaveFunctionCollapse::learn(&[&example], 3).unwrap();
+    assert!(wfc.pattern_count() > 1);
+
+    // every window of the new layout comes from the example
+    let layout = wfc
+        .synthesize(30, 20, &mut ChaCha8Rng::seed_from_u64(9))
+        .unwrap();
+    for y in 0..=layout.height - 3 {
+        for x in 0..=layout.width - 3 {
+            let window: Vec<Symbol> = (0..9)
+                .map(|i| *layout.get((x + i % 3, y + i / 3)))
+                .collect();
+            assert!(
+                wfc.patterns.contains(&window),
+                "\n{}",
+                layout.grid.to_string()
+            );
+        }
+    }
+
+    let maze = wfc.generate(30, 20, 2, 9).unwrap();
+    assert_eq!(maze, wfc.generate(30, 20, 2, 9).unwrap());
+    assert_eq!((maze.width, maze.height), (30, 20));
+    assert!(maze.player_spawn_coord().is_some());
+    assert_eq!(maze.enemy_spawn_coords().len(), 2);
+}
+
+#[test]
+fn test_learn_from_files() {
+    use crate::util::Array2D;
+
+    let dir = std::env::temp_dir().join("ai_wfc_test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("example.txt");
+    let path = path.to_str().unwrap();
+
+    let example = Maze {
+        grid: Array2D::from(
+            "\
+            #########\n\
+            #P..#...#\n\
+            #.#.#.#.#\n\
+            #.#...#E#\n\
+            #########\n"
+                .to_string(),
+        ),
+    };
+    example.save_to_file(path);
+
+    let wfc = WaveFunctionCollapse::learn_from_files(&[path], 2).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(
+        wfc.pattern_count(),
+        WaveFunctionCollapse::learn(&[&example], 2)
+            .unwrap()
+            .pattern_count()
+    );
+    assert!(wfc.generate(16, 12, 1, 0).is_ok());
+
+    assert!(WaveFunctionCollapse::learn_from_files(&["saves/missing.txt"], 2).is_err());
+
+    // ragged rows are reported, not a panic
+    let ragged = dir.join("ragged.txt");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(&ragged, "#####\n#P.#\n").unwrap();
+    let result = WaveFunctionCollapse::learn_from_files(&[ragged.to_str().unwrap()], 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(result.is_err());
+    assert!(WaveFunctionCollapse::learn(&[&example], 10).is_err());
+}
@@ -0,0 +1,273 @@
+use crate::maze::{Coord, Maze};
+use crate::util::file_io;
+use crate::util::pathfinding::walkable_neighbours;
+use crate::util::Direction;
+use anyhow::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// Undirected graph of where agents can walk in a maze, for analyzing the layout in external tools.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NavGraph {
+    pub nodes: Vec<Coord>,
+    /// (node index, node index, cells walked between them)
+    pub edges: Vec<(usize, usize, u32)>,
+}
+
+impl NavGraph {
+    /// every walkable cell is a node, connected to its walkable neighbours
+    pub fn from_cells(maze: &Maze) -> Self {
+        let nodes: Vec<Coord> = maze
+            .iter_rows_first_enumerated()
+            .map(|(coord, _)| coord)
+            .filter(|&coord| maze.is_walkable(coord))
+            .collect();
+        let index: HashMap<Coord, usize> =
+            nodes.iter().enumerate().map(|(i, &c)| (c, i)).collect();
+
+        let mut edges = Vec::new();
+        for (i, &cell) in nodes.iter().enumerate() {
+            // right and up only, so every edge is added once
+            for dir in [Direction::Right, Direction::Up] {
+                let neighbour = dir.step(cell, (maze.width, maze.height));
+                if let Some(&j) = neighbour.and_then(|neighbour| index.get(&neighbour)) {
+                    edges.push((i, j, 1));
+                }
+            }
+        }
+
+        Self { nodes, edges }
+    }
+
+    /// Junctions and dead ends are nodes, and the corridors between them edges weighted by their length.
+    /// Loops without any junction get a node on one of their cells.
+    pub fn corridors(maze: &Maze) -> Self {
+        let degree = |cell: Coord| walkable_neighbours(maze, cell).count();
+        let walkable: Vec<Coord> = maze
+            .iter_rows_first_enumerated()
+            .map(|(coord, _)| coord)
+            .filter(|&coord| maze.is_walkable(coord))
+            .collect();
+
+        let mut nodes: Vec<Coord> = walkable
+            .iter()
+            .copied()
+            .filter(|&cell| degree(cell) != 2)
+            .collect();
+        let mut index: HashMap<Coord, usize> =
+            nodes.iter().enumerate().map(|(i, &c)| (c, i)).collect();
+
+        let mut edges = Vec::new();
+        // (node, first cell) of the corridors already walked, from either end
+        let mut walked = HashSet::new();
+        let mut in_corridor = HashSet::new();
+
+        for &node in &nodes {
+            walk_corridors(
+                maze,
+                node,
+                &index,
+                &mut walked,
+                &mut in_corridor,
+                &mut edges,
+            );
+        }
+
+        // whatever is left are loops of corridor cells
+        for &cell in &walkable {
+            if index.contains_key(&cell) || in_corridor.contains(&cell) {
+                continue;
+            }
+            index.insert(cell, nodes.len());
+            nodes.push(cell);
+            walk_corridors(
+                maze,
+                cell,
+                &index,
+                &mut walked,
+                &mut in_corridor,
+                &mut edges,
+            );
+        }
+
+        Self { nodes, edges }
+    }
+
+    fn node_id((x, y): Coord) -> String {
+        format!("{}_{}", x, y)
+    }
+
+    /// Graphviz DOT, with the cell of each node as its position
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph maze {\n");
+        for &node in &self.nodes {
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [pos=\"{},{}!\"];",
+                Self::node_id(node),
+                node.0,
+                node.1
+            );
+        }
+        for &(a, b, weight) in &self.edges {
+            let _ = writeln!(
+                dot,
+                "    \"{}\" -- \"{}\" [weight={}, label=\"{}\"];",
+                Self::node_id(self.nodes[a]),
+                Self::node_id(self.nodes[b]),
+                weight,
+                weight
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_graphml(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n\
+             \x20   <key id=\"x\" for=\"node\" attr.name=\"x\" attr.type=\"int\"/>\n\
+             \x20   <key id=\"y\" for=\"node\" attr.name=\"y\" attr.type=\"int\"/>\n\
+             \x20   <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"int\"/>\n\
+             \x20   <graph id=\"maze\" edgedefault=\"undirected\">\n",
+        );
+        for &node in &self.nodes {
+            let _ = writeln!(
+                xml,
+                "        <node id=\"{}\"><data key=\"x\">{}</data><data key=\"y\">{}</data></node>",
+                Self::node_id(node),
+                node.0,
+                node.1
+            );
+        }
+        for &(a, b, weight) in &self.edges {
+            let _ = writeln!(
+                xml,
+                "        <edge source=\"{}\" target=\"{}\"><data key=\"weight\">{}</data></edge>",
+                Self::node_id(self.nodes[a]),
+                Self::node_id(self.nodes[b]),
+                weight
+            );
+        }
+        xml.push_str("    </graph>\n</graphml>\n");
+        xml
+    }
+
+    pub fn save_dot(&self, path: &str) -> Result<()> {
+        file_io::write_to_path(path, self.to_dot().as_bytes())
+    }
+
+    pub fn save_graphml(&self, path: &str) -> Result<()> {
+        file_io::write_to_path(path, self.to_graphml().as_bytes())
+    }
+}
+
+/// follows every corridor leaving `from` to the node at its other end
+fn walk_corridors(
+    maze: &Maze,
+    from: Coord,
+    index: &HashMap<Coord, usize>,
+    walked: &mut HashSet<(Coord, Coord)>,
+    in_corridor: &mut HashSet<Coord>,
+    edges: &mut Vec<(usize, usize, u32)>,
+) {
+    for first in walkable_neighbours(maze, from) {
+        if !walked.insert((from, first)) {
+            continue;
+        }
+
+        let (mut previous, mut current, mut length) = (from, first, 1);
+        // corridor cells have exactly two ways to go, in and out
+        while !index.contains_key(&current) {
+            in_corridor.insert(current);
+            let next = walkable_neighbours(maze, current)
+                .find(|&next| next != previous)
+                .unwrap();
+            previous = current;
+            current = next;
+            length += 1;
+        }
+
+        // don't walk the same corridor back from the other end
+        walked.insert((current, previous));
+        edges.push((index[&from], index[&current], length));
+    }
+}
+
+#[test]
+fn test_corridor_graph() {
+    use crate::util::Array2D;
+
+    let maze = Maze {
+        grid: Array2D::from(
+            "\
+            #######\n\
+            #.....#\n\
+            #.###.#\n\
+            #.....#\n\
+            ###.###\n\
+            ###.###\n\
+            #######\n"
+                .to_string(),
+        ),
+    };
+
+    let graph = NavGraph::corridors(&maze);
+    // the junction at (3, 3) and the dead end below it
+    assert_eq!(graph.nodes, vec![(3, 3), (3, 5)]);
+
+    let mut edges: Vec<(Coord, Coord, u32)> = graph
+        .edges
+        .iter()
+        .map(|&(a, b, weight)| (graph.nodes[a], graph.nodes[b], weight))
+        .collect();
+    edges.sort();
+    // the way down to the dead end, and the loop around the wall both ways from the junction
+    assert_eq!(edges, vec![((3, 3), (3, 3), 12), ((3, 3), (3, 5), 2)]);
+
+    let cells = NavGraph::from_cells(&maze);
+    assert_eq!(cells.nodes.len(), 14);
+    assert_eq!(cells.edges.len(), 14);
+}
+
+#[test]
+fn test_corridor_graph_of_a_loop() {
+    use crate::util::Array2D;
+
+    let maze = Maze {
+        grid: Array2D::from(
+            "\
+            #####\n\
+            #...#\n\
+            #.#.#\n\
+            #...#\n\
+            #####\n"
+                .to_string(),
+        ),
+    };
+
+    let graph = NavGraph::corridors(&maze);
+    assert_eq!(graph.nodes.len(), 1);
+    assert_eq!(graph.edges, vec![(0, 0, 8)]);
+}
+
+#[test]
+fn test_export_formats() {
+    let graph = NavGraph {
+        nodes: vec![(0, 0), (4, 2)],
+        edges: vec![(0, 1, 6)],
+    };
+
+    assert_eq!(
+        graph.to_dot(),
+        "graph maze {\n    \"0_0\" [pos=\"0,0!\"];\n    \"4_2\" [pos=\"4,2!\"];\n    \"0_0\" -- \"4_2\" [weight=6, label=\"6\"];\n}\n"
+    );
+
+    let graphml = graph.to_graphml();
+    assert!(graphml
+        .contains("<node id=\"4_2\"><data key=\"x\">4</data><data key=\"y\">2</data></node>"));
+    assert!(graphml
+        .contains("<edge source=\"0_0\" target=\"4_2\"><data key=\"weight\">6</data></edge>"));
+    assert!(graphml.trim_end().ends_with("</graphml>"));
+}
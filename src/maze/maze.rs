@@ -1,3 +1,4 @@
+use crate::util::array2d::{FromChar, ParseError};
 use crate::util::file_io;
 use crate::util::Array2D;
 use anyhow::*;
@@ -23,7 +24,23 @@ pub trait SymbolConsts {
 }
 impl SymbolConsts for Symbol {}
 
-#[derive(Debug, Deref, DerefMut, PartialEq, Eq)]
+/// A symbol read from a maze file, only the ones the game knows are accepted.
+#[derive(Clone)]
+struct KnownSymbol(Symbol);
+
+impl FromChar for KnownSymbol {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            Symbol::PLAYER_SPAWN | Symbol::ENEMY_SPAWN | Symbol::FREE | Symbol::BLOCKED => {
+                Some(Self(c))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deref, DerefMut, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Maze {
     #[deref]
     #[deref_mut]
@@ -54,10 +71,15 @@ impl Maze {
         file_io::write_to_path(path, str.as_bytes()).expect("failed to save maze");
     }
 
-    pub fn load_from_file(path: &str) -> Self {
-        let maze: String = file_io::read_file_to_string(path).expect("failed to load maze file");
-        let grid = Array2D::<Symbol>::from(maze);
-        Self { grid }
+    /// Reads a maze written like `save_to_file` writes it, unknown symbols are an error
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let grid = Array2D::<KnownSymbol>::parse(text)?.map(|symbol| symbol.0);
+        Ok(Self { grid })
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self> {
+        let maze: String = file_io::read_file_to_string(path)?;
+        Self::parse(&maze).with_context(|| format!("failed to read maze file {}", path))
     }
 
     pub fn blocked_coords(&self) -> Vec<Coord> {
@@ -78,6 +100,63 @@ impl Maze {
             .flatten()
             .collect()
     }
+
+    pub fn enemy_spawn_coords(&self) -> Vec<Coord> {
+        self.grid
+            .iter_rows_first_enumerated()
+            .filter(|(_, &symbol)| symbol == Symbol::ENEMY_SPAWN)
+            .map(|(coord, _)| coord)
+            .collect()
+    }
+
+    pub fn is_walkable(&self, coord: Coord) -> bool {
+        *self.grid.get(coord) != Symbol::BLOCKED
+    }
+
+    /// true if a straight line between the centers of the two cells doesn't pass through a blocked cell.
+    /// Lines passing exactly through a corner are blocked if either of the cells touching that corner is.
+    pub fn line_of_sight(&self, from: Coord, to: Coord) -> bool {
+        let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+        let (nx, ny) = (
+            to.0.abs_diff(from.0) as isize,
+            to.1.abs_diff(from.1) as isize,
+        );
+        let step_x = if to.0 > from.0 { 1 } else { -1 };
+        let step_y = if to.1 > from.1 { 1 } else { -1 };
+
+        let blocked = |x: isize, y: isize| !self.is_walkable((x as usize, y as usize));
+
+        if blocked(x, y) {
+            return false;
+        }
+
+        // walk every cell the line passes through (supercover line)
+        let (mut ix, mut iy) = (0, 0);
+        while ix < nx || iy < ny {
+            let decision = (1 + 2 * ix) * ny - (1 + 2 * iy) * nx;
+            if decision == 0 {
+                // through a corner
+                if blocked(x + step_x, y) || blocked(x, y + step_y) {
+                    return false;
+                }
+                x += step_x;
+                y += step_y;
+                ix += 1;
+                iy += 1;
+            } else if decision < 0 {
+                x += step_x;
+                ix += 1;
+            } else {
+                y += step_y;
+                iy += 1;
+            }
+
+            if blocked(x, y) {
+                return false;
+            }
+        }
+        true
+    }
 }
 
 impl Display for Maze {
@@ -92,6 +171,18 @@ impl Display for Maze {
     }
 }
 
+#[test]
+fn test_parse_maze() {
+    use crate::util::array2d::ParseErrorKind;
+
+    let maze = Maze::parse("#.E#\n#P.#\n").unwrap();
+    assert_eq!(maze.player_spawn_coord(), Some((1, 1)));
+
+    let err = Maze::parse("#.E#\n#P?#\n").unwrap_err();
+    assert_eq!((err.line, err.column), (2, 3));
+    assert_eq!(err.kind, ParseErrorKind::UnknownChar('?'));
+}
+
 #[test]
 fn test_save_load_maze() {
     let mut maze = Maze::new_empty(10, 5);
@@ -105,10 +196,26 @@ fn test_save_load_maze() {
     maze.set((9, 4), Symbol::PLAYER_SPAWN);
 
     // save and load
-    let file_path = "saves/test_save.txt";
+    let file_path = std::env::temp_dir().join("ai_maze_test.txt");
+    let file_path = file_path.to_str().unwrap();
     maze.save_to_file(file_path);
-    let loaded_maze = Maze::load_from_file(file_path);
+    let loaded_maze = Maze::load_from_file(file_path).unwrap();
+    std::fs::remove_file(file_path).unwrap();
 
     // assert equal
     assert_eq!(maze, loaded_maze);
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_maze() {
+    let maze = Maze {
+        grid: Array2D::from("#.E#\n#P.#\n".to_string()),
+    };
+
+    let ron = ron::to_string(&maze).unwrap();
+    assert_eq!(ron::from_str::<Maze>(&ron).unwrap(), maze);
+
+    let json = serde_json::to_string(&maze).unwrap();
+    assert_eq!(serde_json::from_str::<Maze>(&json).unwrap(), maze);
+}
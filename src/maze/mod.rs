@@ -1,5 +1,18 @@
 mod maze;
 pub use maze::*;
 
+mod graph;
+pub use graph::*;
+
+pub mod generate;
+
+mod stats;
+pub use stats::*;
+
+mod transform;
+
+mod validate;
+pub use validate::*;
+
 mod plugin;
 pub use plugin::*;
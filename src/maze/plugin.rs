@@ -7,7 +7,7 @@ use std::thread::{current, spawn};
 
 use crate::application::{WINDOW_HEIGHT, WINDOW_WIDTH};
 use crate::input::{MouseLeftEvent, MousePos, MouseRightEvent, PlayerInputPlugin};
-use crate::maze::{Coord, Maze, Symbol, SymbolConsts};
+use crate::maze::{Coord, Maze, MazeIssue, NavGraph, Symbol, SymbolConsts};
 use crate::{grid_plugin, Player};
 pub use resources::*;
 
@@ -43,16 +43,33 @@ mod entities {
             .id()
         }
     }
+
+    /// A dormant enemy spawn. Nothing comes out of it until the director activates it.
+    #[derive(Component)]
+    pub struct EnemySpawn;
+
+    impl EnemySpawn {
+        pub(crate) fn spawn(cmd: &mut Commands, maze: &MazeResource, translation: Vec2) -> Entity {
+            cmd.spawn_bundle(SpriteBundle {
+                // below the enemies coming out of it
+                transform: Transform::from_translation(Vec3::new(translation.x, translation.y, -1.)),
+                sprite: maze.square_sprite(Color::MAROON),
+                ..Default::default()
+            })
+            .insert(Self)
+            .id()
+        }
+    }
 }
 
 mod resources {
-    use crate::maze::{Coord, Maze, Symbol, SymbolConsts, Wall};
+    use crate::maze::{Coord, EnemySpawn, Maze, Symbol, SymbolConsts, Wall};
     use bevy::log;
     use bevy::prelude::*;
     use derive_more::{Deref, DerefMut};
     use std::collections::HashMap;
 
-    use crate::{setup_entities, Enemy, Player};
+    use crate::{setup_entities, Player};
     use std::default::Default;
 
     #[derive(Deref, DerefMut, Component)]
@@ -64,6 +81,8 @@ mod resources {
         pub screen_dimensions: (f32, f32),
         // entity id spawned at each coordinate
         pub spawned_entities: HashMap<Coord, Entity>,
+        // coords whose symbol changed since the last time someone took them, for replanning paths
+        pub changed_coords: Vec<Coord>,
     }
 
     impl MazeResource {
@@ -72,12 +91,14 @@ mod resources {
                 cmd.entity(entity).despawn_recursive();
             }
             self.loaded_maze.grid.set(coord, Symbol::FREE);
+            self.changed_coords.push(coord);
         }
 
         pub fn spawn_entity(&mut self, cmd: &mut Commands, coord: Coord, symbol: Symbol) {
             log::info!("placed entity at: {:?}", coord);
             // set it on the loaded maze
             self.loaded_maze.grid.set(coord, symbol);
+            self.changed_coords.push(coord);
             // spawn
             let pos = self.screen_pos_from_maze_coord(coord);
 
@@ -85,9 +106,10 @@ mod resources {
                 Symbol::FREE => return,
                 Symbol::BLOCKED => Wall::spawn(cmd, &self, pos),
                 Symbol::PLAYER_SPAWN => Player::spawn(cmd, pos),
-                Symbol::ENEMY_SPAWN => Enemy::spawn(cmd, pos),
+                Symbol::ENEMY_SPAWN => EnemySpawn::spawn(cmd, &self, pos),
                 _ => {
-                    panic!("not implemented {}", symbol)
+                    log::error!("can't spawn unknown symbol {:?} at {:?}", symbol, coord);
+                    return;
                 }
             };
             self.spawned_entities.insert(coord, entity);
@@ -119,6 +141,7 @@ mod resources {
                 square_block_side_length,
                 screen_dimensions: (screen_width, screen_height),
                 spawned_entities: HashMap::new(),
+                changed_coords: Vec::new(),
             }
         }
 
@@ -129,6 +152,12 @@ mod resources {
             (maze_x, maze_y)
         }
 
+        /// maze coord of a translation in world space (origin at the center of the screen)
+        pub fn maze_coord_from_world_pos(&self, world_pos: &Vec2) -> Coord {
+            let screen_pos = *world_pos + Vec2::from(self.screen_dimensions) / 2.;
+            self.maze_coord_from_screen_pos(&screen_pos)
+        }
+
         pub fn screen_pos_from_maze_coord(&self, (maze_x, maze_y): Coord) -> Vec2 {
             let square_side = self.square_block_side_length.clone();
 
@@ -148,6 +177,8 @@ mod resources {
 }
 
 const MAZE_SAVE_FILE: &str = "saves/save.txt";
+const GRAPH_DOT_FILE: &str = "saves/save.dot";
+const GRAPH_GRAPHML_FILE: &str = "saves/save.graphml";
 
 pub struct MazePlugin;
 impl MazePlugin {
@@ -166,7 +197,9 @@ impl Plugin for MazePlugin {
                 .with_system(Self::on_mouse_left.system())
                 .with_system(Self::on_mouse_right.system())
                 .with_system(Self::save_maze_play_game.system())
-                .with_system(Self::load_maze.system()),
+                .with_system(Self::load_maze.system())
+                .with_system(Self::export_graph.system())
+                .with_system(Self::fill_region.system()),
         );
     }
 }
@@ -242,39 +275,100 @@ impl MazePlugin {
         }
 
         if play_key {
-            state.set(GameState::PlayGame);
+            let issues = maze.validate();
+            for issue in &issues {
+                log::warn!("maze issue: {}", issue);
+            }
+
+            if issues.iter().any(MazeIssue::is_error) {
+                log::error!("fix the maze issues above before playing");
+            } else {
+                state.set(GameState::PlayGame);
+            }
+        }
+    }
+
+    /// writes the corridor graph of the maze for external tools
+    fn export_graph(maze: Res<MazeResource>, input: Res<Input<KeyCode>>) {
+        if input.just_pressed(KeyCode::G) {
+            let graph = NavGraph::corridors(&maze);
+            let saved = graph
+                .save_dot(GRAPH_DOT_FILE)
+                .and_then(|_| graph.save_graphml(GRAPH_GRAPHML_FILE));
+
+            match saved {
+                Ok(_) => log::info!(
+                    "exported graph with {} nodes and {} edges to {} and {}",
+                    graph.nodes.len(),
+                    graph.edges.len(),
+                    GRAPH_DOT_FILE,
+                    GRAPH_GRAPHML_FILE
+                ),
+                Err(err) => log::error!("failed to export graph: {:?}", err),
+            }
         }
     }
 
+    /// fills the open region under the mouse with walls, or clears the walls under it
+    fn fill_region(
+        mut cmd: Commands,
+        mut maze: ResMut<MazeResource>,
+        mouse_pos: Res<MousePos>,
+        input: Res<Input<KeyCode>>,
+    ) {
+        if !input.just_pressed(KeyCode::F) {
+            return;
+        }
+        let start = maze.maze_coord_from_screen_pos(&mouse_pos);
+        if !maze.in_bounds(start) {
+            return;
+        }
+
+        // spawns are kept, they don't belong to the region
+        let target = *maze.get(start);
+        let (region, fill) = match target {
+            Symbol::FREE => (maze.flood_fill(start, |&s| s == Symbol::FREE), Symbol::BLOCKED),
+            Symbol::BLOCKED => (maze.flood_fill(start, |&s| s == Symbol::BLOCKED), Symbol::FREE),
+            _ => return,
+        };
+        for &coord in &region {
+            maze.free_coord(&mut cmd, coord);
+            maze.spawn_entity(&mut cmd, coord, fill);
+        }
+        log::info!("filled {} cells from {:?}", region.len(), start);
+    }
+
     fn load_maze(mut cmd: Commands, mut maze: ResMut<MazeResource>, input: Res<Input<KeyCode>>) {
         // load file
         if input.just_pressed(KeyCode::L) {
+            let (mut new_maze, issues) = match Maze::load_validated(MAZE_SAVE_FILE) {
+                Ok(loaded) => loaded,
+                Err(err) => {
+                    log::error!("failed to load maze: {:?}", err);
+                    return;
+                }
+            };
+
+            for issue in &issues {
+                log::warn!("{}: {}", MAZE_SAVE_FILE, issue);
+                // leave out what can't be spawned
+                match *issue {
+                    MazeIssue::DuplicatePlayerSpawn { coord }
+                    | MazeIssue::UnknownSymbol { coord, .. } => new_maze.set(coord, Symbol::FREE),
+                    _ => {}
+                }
+            }
+
             for (_coord, entity) in maze.spawned_entities.drain() {
                 cmd.entity(entity).despawn_recursive();
             }
 
-            let new_maze = Maze::load_from_file(MAZE_SAVE_FILE);
-
-            let mut player_set = false;
+            // the loaded maze may not be the size of the old one
+            maze.loaded_maze = Maze::new_empty(new_maze.width, new_maze.height);
             for (coord, &symbol) in new_maze.grid.iter_rows_first_enumerated() {
-                if symbol == Symbol::PLAYER_SPAWN {
-                    if player_set {
-                        let err_msg = "you may only have one player spawn!";
-                        log::error!(err_msg);
-                        panic!("{}", err_msg);
-                    }
-                    player_set = true;
-                }
                 maze.spawn_entity(&mut cmd, coord, symbol);
             }
 
-            for maze_coord in new_maze.blocked_coords() {
-                let screen_pos = maze.screen_pos_from_maze_coord(maze_coord);
-
-                let wall_entity = Wall::spawn(&mut cmd, &maze, screen_pos);
-                maze.spawned_entities.insert(maze_coord, wall_entity);
-            }
-
             maze.loaded_maze = new_maze;
         }
     }
@@ -0,0 +1,192 @@
+use crate::maze::{Coord, Maze, Symbol, SymbolConsts};
+use crate::util::pathfinding::{distances_to, walkable_neighbours};
+
+/// Numbers describing the layout of a maze, to compare levels with each other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MazeStats {
+    pub free_cells: usize,
+    /// free cells with a single way out
+    pub dead_ends: usize,
+    /// free cells with three or four ways out
+    pub junctions: usize,
+    /// average number of ways out of a junction, 0 without junctions
+    pub branching_factor: f32,
+    /// steps from the player spawn to each enemy spawn, None if it can't be reached
+    pub enemy_distances: Vec<(Coord, Option<u32>)>,
+    /// independent loops in the free cells (the cyclomatic number of their graph), every loop is another way
+    ///  around an enemy
+    pub loops: usize,
+    /// cells in the largest rectangle of free cells
+    pub largest_open_area: usize,
+    /// rough estimate, only meaningful compared to the difficulty of other mazes
+    pub difficulty: f32,
+}
+
+impl MazeStats {
+    pub fn new(maze: &Maze) -> Self {
+        let free: Vec<Coord> = maze
+            .iter_rows_first_enumerated()
+            .map(|(coord, _)| coord)
+            .filter(|&coord| maze.is_walkable(coord))
+            .collect();
+
+        let ways_out: Vec<usize> = free
+            .iter()
+            .map(|&cell| walkable_neighbours(maze, cell).count())
+            .collect();
+        let dead_ends = ways_out.iter().filter(|&&ways| ways == 1).count();
+        let junction_ways: Vec<usize> =
+            ways_out.iter().copied().filter(|&ways| ways >= 3).collect();
+        let branching_factor = if junction_ways.is_empty() {
+            0.
+        } else {
+            junction_ways.iter().sum::<usize>() as f32 / junction_ways.len() as f32
+        };
+
+        let enemy_distances = match maze.player_spawn_coord() {
+            Some(player) => {
+                let distances = distances_to(maze, player);
+                maze.enemy_spawn_coords()
+                    .into_iter()
+                    .map(|enemy| (enemy, distances[enemy]))
+                    .collect()
+            }
+            None => maze
+                .enemy_spawn_coords()
+                .into_iter()
+                .map(|enemy| (enemy, None))
+                .collect(),
+        };
+
+        // every edge past a spanning tree of each component closes a loop
+        let edges = ways_out.iter().sum::<usize>() / 2;
+        let components = maze
+            .label_components(|&symbol| symbol != Symbol::BLOCKED)
+            .count();
+        let loops = edges + components - free.len();
+
+        let mut stats = Self {
+            free_cells: free.len(),
+            dead_ends,
+            junctions: junction_ways.len(),
+            branching_factor,
+            enemy_distances,
+            loops,
+            largest_open_area: largest_open_rectangle(maze),
+            difficulty: 0.,
+        };
+        stats.difficulty = stats.estimate_difficulty();
+        stats
+    }
+
+    // more enemies closer to the player make a level harder, dead ends trap the player and loops let it get
+    //  away
+    fn estimate_difficulty(&self) -> f32 {
+        // an enemy right next to the player counts 1, one 10 steps away 0.5
+        let pressure: f32 = self
+            .enemy_distances
+            .iter()
+            .filter_map(|&(_, distance)| distance)
+            .map(|distance| 10. / (10. + distance as f32))
+            .sum();
+
+        let cells = self.free_cells.max(1) as f32;
+        let traps = 1. + 10. * self.dead_ends as f32 / cells;
+        let escapes = 1. + 10. * self.loops as f32 / cells;
+        pressure * traps / escapes
+    }
+}
+
+impl Maze {
+    pub fn stats(&self) -> MazeStats {
+        MazeStats::new(self)
+    }
+}
+
+/// sorts the levels from the easiest to the hardest
+pub fn sort_by_difficulty<T>(levels: &mut Vec<T>, maze_of: impl Fn(&T) -> &Maze) {
+    let mut keyed: Vec<(f32, T)> = levels
+        .drain(..)
+        .map(|level| (maze_of(&level).stats().difficulty, level))
+        .collect();
+    keyed.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    levels.extend(keyed.into_iter().map(|(_, level)| level));
+}
+
+/// area of the largest rectangle of free cells, by the largest rectangle under the histogram of free cells
+///  stacked up to each row
+fn largest_open_rectangle(maze: &Maze) -> usize {
+    let mut heights = vec![0; maze.width];
+    let mut largest = 0;
+
+    for y in 0..maze.height {
+        for (x, height) in heights.iter_mut().enumerate() {
+            *height = if maze.is_walkable((x, y)) {
+                *height + 1
+            } else {
+                0
+            };
+        }
+
+        // indices of increasing heights, every rectangle ends where a lower column comes up
+        let mut stack: Vec<usize> = Vec::new();
+        for x in 0..=maze.width {
+            let height = heights.get(x).copied().unwrap_or(0);
+            while let Some(&top) = stack.last() {
+                if heights[top] < height {
+                    break;
+                }
+                stack.pop();
+                let left = stack.last().map_or(0, |&left| left + 1);
+                largest = largest.max(heights[top] * (x - left));
+            }
+            stack.push(x);
+        }
+    }
+    largest
+}
+
+#[test]
+fn test_maze_stats() {
+    use crate::util::Array2D;
+
+    let maze = Maze {
+        grid: Array2D::from(
+            "\
+            #########\n\
+            #P..#...#\n\
+            #.#.#.#.#\n\
+            #.......#\n\
+            ###.###.#\n\
+            #E..#E..#\n\
+            #########\n"
+                .to_string(),
+        ),
+    };
+
+    let stats = maze.stats();
+    assert_eq!(stats.free_cells, 25);
+    // both enemies are in one
+    assert_eq!(stats.dead_ends, 2);
+    assert_eq!(stats.junctions, 3);
+    assert_eq!(stats.branching_factor, 10. / 3.);
+    assert_eq!(
+        stats.enemy_distances,
+        vec![((1, 5), Some(8)), ((5, 5), Some(12))]
+    );
+    assert_eq!(stats.loops, 2);
+    assert_eq!(stats.largest_open_area, 7);
+
+    // a single enemy further away
+    let mut easier = Maze {
+        grid: Array2D::from(maze.grid.to_string()),
+    };
+    easier.set((1, 5), '.');
+    easier.set((5, 5), '.');
+    easier.set((7, 5), 'E');
+    assert!(easier.stats().difficulty < stats.difficulty);
+
+    let mut levels = vec![("hard", maze), ("easy", easier)];
+    sort_by_difficulty(&mut levels, |(_, maze)| maze);
+    assert_eq!(levels[0].0, "easy");
+}
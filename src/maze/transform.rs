@@ -0,0 +1,109 @@
+use crate::maze::{Coord, Maze, Symbol, SymbolConsts};
+use crate::util::Array2D;
+
+/// The `Array2D` transforms for whole mazes, to build symmetric and composite levels out of existing pieces.
+/// Spawns move along with the cells, so composing copies of a piece also copies its player spawn. Such a
+///  level only passes `validate` after `keep_first_player_spawn`.
+impl Maze {
+    pub fn rotate_90(&self) -> Self {
+        self.grid.rotate_90().into()
+    }
+
+    pub fn rotate_180(&self) -> Self {
+        self.grid.rotate_180().into()
+    }
+
+    pub fn rotate_270(&self) -> Self {
+        self.grid.rotate_270().into()
+    }
+
+    pub fn flip_horizontal(&self) -> Self {
+        self.grid.flip_horizontal().into()
+    }
+
+    pub fn flip_vertical(&self) -> Self {
+        self.grid.flip_vertical().into()
+    }
+
+    pub fn transpose(&self) -> Self {
+        self.grid.transpose().into()
+    }
+
+    pub fn crop(&self, origin: Coord, width: usize, height: usize) -> Self {
+        self.grid.crop(origin, width, height).into()
+    }
+
+    pub fn resize(&self, width: usize, height: usize, fill: Symbol) -> Self {
+        self.grid.resize(width, height, fill).into()
+    }
+
+    pub fn pad(&self, left: usize, right: usize, bottom: usize, top: usize, fill: Symbol) -> Self {
+        self.grid.pad(left, right, bottom, top, fill).into()
+    }
+
+    pub fn stitch_horizontal(&self, other: &Maze, fill: Symbol) -> Self {
+        self.grid.stitch_horizontal(&other.grid, fill).into()
+    }
+
+    pub fn stitch_vertical(&self, other: &Maze, fill: Symbol) -> Self {
+        self.grid.stitch_vertical(&other.grid, fill).into()
+    }
+
+    /// Frees every player spawn after the first one in the order `validate` finds them
+    pub fn keep_first_player_spawn(&self) -> Self {
+        let mut maze = self.clone();
+        let duplicates: Vec<Coord> = self
+            .iter_rows_first_enumerated()
+            .filter(|(_, &symbol)| symbol == Symbol::PLAYER_SPAWN)
+            .map(|(coord, _)| coord)
+            .skip(1)
+            .collect();
+        for coord in duplicates {
+            maze.set(coord, Symbol::FREE);
+        }
+        maze
+    }
+}
+
+impl From<Array2D<Symbol>> for Maze {
+    fn from(grid: Array2D<Symbol>) -> Self {
+        Self { grid }
+    }
+}
+
+#[test]
+fn test_symmetric_maze() {
+    use crate::maze::MazeIssue;
+
+    let piece = Maze::from(Array2D::from(
+        "\
+        ####\n\
+        #P.#\n\
+        #..E\n"
+            .to_string(),
+    ));
+
+    // mirrored to the right and up
+    let half = piece.stitch_horizontal(&piece.flip_horizontal(), Symbol::BLOCKED);
+    let level = half.stitch_vertical(&half.flip_vertical(), Symbol::BLOCKED);
+
+    assert_eq!((level.width, level.height), (8, 6));
+    assert_eq!(level.enemy_spawn_coords().len(), 4);
+    // every copy of the piece brought its player spawn along
+    let duplicates = level
+        .validate()
+        .into_iter()
+        .filter(|issue| matches!(issue, MazeIssue::DuplicatePlayerSpawn { .. }))
+        .count();
+    assert_eq!(duplicates, 3);
+    let playable = level.keep_first_player_spawn();
+    assert!(playable.validate().is_empty());
+    assert_eq!(playable.player_spawn_coord(), level.player_spawn_coord());
+    assert_eq!(level.flip_horizontal(), level);
+    assert_eq!(level.rotate_180(), level);
+    assert_eq!(level.crop((0, 0), 4, 3), piece);
+    assert_eq!(
+        level.pad(1, 1, 1, 1, Symbol::BLOCKED).crop((1, 1), 8, 6),
+        level
+    );
+}
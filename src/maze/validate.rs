@@ -0,0 +1,156 @@
+use crate::maze::{Coord, Maze, Symbol, SymbolConsts};
+use crate::util::pathfinding::walkable_neighbours;
+use anyhow::*;
+use std::fmt::{Display, Formatter};
+
+/// Something wrong with a maze that the game can't deal with, or that makes it unplayable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MazeIssue {
+    MissingPlayerSpawn,
+    /// every player spawn after the first one
+    DuplicatePlayerSpawn {
+        coord: Coord,
+    },
+    UnknownSymbol {
+        coord: Coord,
+        symbol: Symbol,
+    },
+    UnreachableEnemy {
+        coord: Coord,
+    },
+    /// a spawn walled in on every side, whatever spawns there can't move
+    SpawnInWall {
+        coord: Coord,
+    },
+}
+
+impl MazeIssue {
+    /// the maze can't be played with this issue, the others only make it a bad maze
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            Self::MissingPlayerSpawn
+                | Self::DuplicatePlayerSpawn { .. }
+                | Self::UnknownSymbol { .. }
+        )
+    }
+}
+
+impl Display for MazeIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingPlayerSpawn => write!(f, "there is no player spawn"),
+            Self::DuplicatePlayerSpawn { coord } => {
+                write!(
+                    f,
+                    "another player spawn at {:?}, only one is allowed",
+                    coord
+                )
+            }
+            Self::UnknownSymbol { coord, symbol } => {
+                write!(f, "unknown symbol {:?} at {:?}", symbol, coord)
+            }
+            Self::UnreachableEnemy { coord } => {
+                write!(f, "the enemy at {:?} can't reach the player", coord)
+            }
+            Self::SpawnInWall { coord } => write!(f, "the spawn at {:?} is walled in", coord),
+        }
+    }
+}
+
+impl Maze {
+    pub fn validate(&self) -> Vec<MazeIssue> {
+        let mut issues = Vec::new();
+
+        let mut players = Vec::new();
+        for (coord, &symbol) in self.iter_rows_first_enumerated() {
+            match symbol {
+                Symbol::PLAYER_SPAWN => players.push(coord),
+                Symbol::ENEMY_SPAWN | Symbol::FREE | Symbol::BLOCKED => {}
+                _ => issues.push(MazeIssue::UnknownSymbol { coord, symbol }),
+            }
+        }
+
+        match players.split_first() {
+            None => issues.push(MazeIssue::MissingPlayerSpawn),
+            Some((_, duplicates)) => issues.extend(
+                duplicates
+                    .iter()
+                    .map(|&coord| MazeIssue::DuplicatePlayerSpawn { coord }),
+            ),
+        }
+
+        let enemies = self.enemy_spawn_coords();
+        for &coord in players.iter().chain(&enemies) {
+            if walkable_neighbours(self, coord).next().is_none() {
+                issues.push(MazeIssue::SpawnInWall { coord });
+            }
+        }
+
+        if let Some(&player) = players.first() {
+            let regions = self.label_components(|&symbol| symbol != Symbol::BLOCKED);
+            issues.extend(
+                enemies
+                    .into_iter()
+                    .filter(|&coord| regions.labels[coord] != regions.labels[player])
+                    .map(|coord| MazeIssue::UnreachableEnemy { coord }),
+            );
+        }
+
+        issues
+    }
+
+    /// Loads a maze file along with its issues. Only a file that can't be read as a grid at all is an error.
+    pub fn load_validated(path: &str) -> Result<(Self, Vec<MazeIssue>)> {
+        let maze = Self::load_from_file(path)?;
+        let issues = maze.validate();
+        Ok((maze, issues))
+    }
+}
+
+#[test]
+fn test_validate_maze() {
+    use crate::util::array2d::ParseErrorKind;
+
+    let mut maze = Maze::parse(
+        "\
+        #######\n\
+        #P..#E#\n\
+        #.#.###\n\
+        #P..###\n\
+        ##E####\n",
+    )
+    .unwrap();
+    // symbols can still be set in code that no file would contain
+    maze.set((3, 2), '?');
+    assert_eq!(
+        maze.validate(),
+        vec![
+            MazeIssue::UnknownSymbol {
+                coord: (3, 2),
+                symbol: '?'
+            },
+            MazeIssue::DuplicatePlayerSpawn { coord: (1, 3) },
+            MazeIssue::SpawnInWall { coord: (5, 1) },
+            MazeIssue::UnreachableEnemy { coord: (5, 1) },
+        ]
+    );
+    assert!(maze.validate().iter().any(MazeIssue::is_error));
+
+    let maze = Maze::parse("E.#\n..#\n").unwrap();
+    assert_eq!(maze.validate(), vec![MazeIssue::MissingPlayerSpawn]);
+
+    let maze = Maze::parse("P.#\n.E#\n").unwrap();
+    assert!(maze.validate().is_empty());
+
+    // ragged rows don't make a grid, they are a parse error and not an issue
+    let err = Maze::parse("P.#\n.E\n").unwrap_err();
+    assert_eq!((err.line, err.column), (2, 3));
+    assert_eq!(
+        err.kind,
+        ParseErrorKind::RaggedRow {
+            len: 2,
+            expected: 3
+        }
+    );
+}
@@ -1,6 +1,7 @@
 use crate::resources_and_components::{CollidedWith, CollisionData, SpriteCollider, Velocity};
 use crate::util::*;
 use crate::{grid_plugin, Enemy, MazeResource, Player, fixed_time_step_dependant_state};
+use crate::ai::{MctsAgent, SolverAgent};
 use anyhow::Result;
 use bevy::core::FixedTimestep;
 use bevy::ecs::schedule::ShouldRun;
@@ -19,11 +20,42 @@ pub mod components {
     use derive_more::{Deref, DerefMut};
 
     #[derive(Deref, DerefMut, Component)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct MovementSpeed(
         #[deref]
         #[deref_mut]
         pub f32,
     );
+
+    /// enemies with a path walk it cell by cell instead of heading straight for the player.
+    /// The same cell twice in a row means waiting there for as long as it takes to walk one cell.
+    #[derive(Debug, Default, Component)]
+    pub struct PathFollower {
+        pub path: Vec<(usize, usize)>,
+        // index of the waypoint currently walked towards
+        pub next: usize,
+        // seconds left to stand still
+        pub wait: f32,
+    }
+
+    impl PathFollower {
+        pub fn new(path: Vec<(usize, usize)>) -> Self {
+            Self {
+                path,
+                next: 0,
+                wait: 0.,
+            }
+        }
+
+        pub fn current_waypoint(&self) -> Option<(usize, usize)> {
+            self.path.get(self.next).copied()
+        }
+
+        /// the rest of the path, from the waypoint reached last
+        pub fn remaining(&self) -> &[(usize, usize)] {
+            &self.path[self.next.saturating_sub(1).min(self.path.len())..]
+        }
+    }
 }
 
 #[derive(Component)]
@@ -42,6 +74,8 @@ fn collision_system(
     mut player: Query<(&mut Transform, &Collider), With<Player>>,
     enemies_and_walls: Query<(Entity, &Transform, &Collider), (Without<Bullet>, Without<Player>)>,
     bullets: Query<(Entity, &Transform, &Collider), (With<Bullet>, Without<Player>)>,
+    mut enemy_killed_events: EventWriter<EnemyKilled>,
+    mut player_hit_events: EventWriter<PlayerHit>,
 ) {
     let square_side_size = maze.square_block_side_length;
     let (mut player_transform, player_collider) = player.single_mut();
@@ -61,10 +95,14 @@ fn collision_system(
             if collision.is_some() {
                 if let Collider::Enemy = *collided_collider {
                     cmd.entity(collided_entity).despawn();
+                    enemy_killed_events.send(EnemyKilled {
+                        position: collided_transform.translation.truncate(),
+                    });
                 }
 
                 if let Collider::Solid = *collided_collider {
                     log::warn!("GAME OVER!");
+                    player_hit_events.send(PlayerHit);
                 }
             }
         }
@@ -82,6 +120,9 @@ fn collision_system(
                 if collision.is_some() {
                     if let Collider::Enemy = *collided_collider {
                         cmd.entity(collided_entity).despawn_recursive();
+                        enemy_killed_events.send(EnemyKilled {
+                            position: collided_transform.translation.truncate(),
+                        });
                     }
                     cmd.entity(bullet_entity).despawn_recursive();
                 }
@@ -129,8 +170,8 @@ pub(crate) struct MovementPlugin;
 impl MovementPlugin {
     pub const DEPENDENCY: &'static str = "MovementPlugin";
 }
-const MOVEMENT_SYSTEM: &str = "movement_system";
-const UPDATE_VELOCITY_COMPONENTS: &str = "update vel comps";
+pub(crate) const MOVEMENT_SYSTEM: &str = "movement_system";
+pub(crate) const UPDATE_VELOCITY_COMPONENTS: &str = "update vel comps";
 
 use crate::application::TIME_STEP;
 
@@ -168,12 +209,19 @@ enum MovementPluginState {
     MoveEntities,
 }
 
+// the player, unless something else has taken over its controls
+type PlayerControlled = (With<Player>, Without<MctsAgent>, Without<SolverAgent>);
+
 fn update_player_velocity_system(
     time: Res<Time>,
     input: Res<AxisInput>,
-    mut q: Query<(&mut Velocity, &MovementSpeed), With<Player>>,
+    mut q: Query<(&mut Velocity, &MovementSpeed), PlayerControlled>,
 ) {
-    let (mut vel, movement_speed) = q.single_mut();
+    // the player is driven by the search or a maze solver instead while it has an MctsAgent or SolverAgent
+    let (mut vel, movement_speed) = match q.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
 
     vel.velocity = Vec2::ZERO;
 
@@ -186,9 +234,13 @@ fn update_player_velocity_system(
 
 fn update_enemy_velocities_system(
     time: Res<Time>,
+    maze: Res<MazeResource>,
     target: Query<&Transform, With<Player>>,
     compute_pool: Res<ComputeTaskPool>,
-    mut enemy: Query<(&Transform, &mut Velocity, &MovementSpeed), With<Enemy>>,
+    mut enemy: Query<
+        (&Transform, &mut Velocity, &MovementSpeed, &mut PathFollower),
+        (With<Enemy>, Without<MctsAgent>),
+    >,
 ) {
     let player_transform = target.single();
     //single();
@@ -199,9 +251,32 @@ fn update_enemy_velocities_system(
 
     let enemy_count = 1;
 
-    for (transform, mut vel, movement_speed) in enemy.iter_mut() {
+    for (transform, mut vel, movement_speed, mut path_follower) in enemy.iter_mut() {
         let agent_pos = transform.translation;
-        let target_dir = (player_pos - agent_pos).normalize_or_zero();
+
+        // walk towards the next waypoint of the path if there is one, otherwise straight at the player
+        let mut target_pos = player_pos;
+        let step = movement_speed.0 * dt;
+        let seconds_per_cell = maze.square_block_side_length / movement_speed.0;
+
+        while let Some(waypoint) = path_follower.current_waypoint() {
+            let waypoint_pos = to_vec3(&maze.screen_pos_from_maze_coord(waypoint));
+            if waypoint_pos.distance(agent_pos) > step {
+                target_pos = waypoint_pos;
+                break;
+            }
+            path_follower.next += 1;
+            if path_follower.current_waypoint() == Some(waypoint) {
+                path_follower.wait += seconds_per_cell;
+            }
+        }
+
+        if path_follower.wait > 0. {
+            path_follower.wait -= dt;
+            target_pos = agent_pos;
+        }
+
+        let target_dir = (target_pos - agent_pos).normalize_or_zero();
 
         //let x = pathfinding::path_find(&to_vec2(&agent_pos), &to_vec2(&player_pos));
 
@@ -254,7 +329,7 @@ use bevy::sprite::collide_aabb::{collide, Collision};
 use bevy::tasks::ComputeTaskPool;
 use bevy::utils::tracing::Instrument;
 use bevy::utils::tracing::instrument::WithSubscriber;
-use crate::battle::Bullet;
+use crate::battle::{Bullet, EnemyKilled, PlayerHit};
 
 #[derive(Debug)]
 pub struct Collisions(Vec<CollisionData>);
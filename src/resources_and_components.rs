@@ -32,7 +32,36 @@ pub mod collisions {
 }
 
 #[derive(Debug, Default, Clone, Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Velocity {
     pub velocity: Vec2, // accumulated velocity since the last frame update (resets on update after moving the objects)
     pub previous_velocity: Vec2,
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_components() {
+    use crate::grid_plugin::GridCoord;
+    use crate::movement::MovementSpeed;
+
+    let velocity = Velocity {
+        velocity: Vec2::new(1.5, -2.),
+        previous_velocity: Vec2::new(0., 3.),
+    };
+    let coord = GridCoord { x: 4, y: 7 };
+
+    let ron = ron::to_string(&(velocity.clone(), coord, MovementSpeed(120.))).unwrap();
+    let (loaded_velocity, loaded_coord, speed): (Velocity, GridCoord, MovementSpeed) =
+        ron::from_str(&ron).unwrap();
+    assert_eq!(loaded_velocity.velocity, velocity.velocity);
+    assert_eq!(loaded_velocity.previous_velocity, velocity.previous_velocity);
+    assert_eq!(loaded_coord, coord);
+    assert_eq!(*speed, 120.);
+
+    let json = serde_json::to_string(&(velocity.clone(), coord, MovementSpeed(120.))).unwrap();
+    let (loaded_velocity, loaded_coord, speed): (Velocity, GridCoord, MovementSpeed) =
+        serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded_velocity.velocity, velocity.velocity);
+    assert_eq!(loaded_coord, coord);
+    assert_eq!(*speed, 120.);
+}
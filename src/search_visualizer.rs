@@ -0,0 +1,191 @@
+use crate::application::GameState;
+use crate::input::PlayerInputPlugin;
+use crate::maze::{Coord, MazeResource};
+use crate::util::pathfinding::{AStarSearch, SearchAlgorithm, SearchStatus};
+use bevy::log;
+use bevy::prelude::*;
+
+pub use resources::*;
+mod resources {
+    use crate::util::pathfinding::{AStarSearch, SearchAlgorithm};
+
+    pub struct SearchVisualizer {
+        pub enabled: bool,
+        pub algorithm: SearchAlgorithm,
+        pub search: Option<AStarSearch>,
+        // step every frame instead of waiting for the step key
+        pub running: bool,
+        // the overlay is out of date
+        pub redraw: bool,
+        // length of the maze's changed cells when the search was last checked against them
+        pub seen_changes: usize,
+    }
+
+    impl Default for SearchVisualizer {
+        fn default() -> Self {
+            Self {
+                enabled: false,
+                algorithm: SearchAlgorithm::AStar,
+                search: None,
+                running: false,
+                redraw: false,
+                seen_changes: 0,
+            }
+        }
+    }
+}
+
+pub use components::*;
+mod components {
+    use bevy::prelude::*;
+
+    // a colored square on top of the maze showing the state of a cell in the search
+    #[derive(Component)]
+    pub struct SearchOverlay;
+}
+
+/// Debug mode for the map editor that runs a search from the player spawn to the first enemy spawn one
+///  expansion at a time.
+///
+/// V: toggle, N: step, R: run/pause, X: reset, Tab: next algorithm
+pub struct SearchVisualizerPlugin;
+
+impl Plugin for SearchVisualizerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SearchVisualizer::default())
+            .add_system_set(
+                SystemSet::on_update(GameState::BuildMap)
+                    .after(PlayerInputPlugin::DEPENDENCY)
+                    .with_system(Self::input_system.label("search_visualizer_input"))
+                    .with_system(Self::draw_system.after("search_visualizer_input")),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::BuildMap).with_system(Self::disable_system),
+            );
+    }
+}
+
+impl SearchVisualizerPlugin {
+    const OPEN_COLOR: Color = Color::rgba(0.2, 0.8, 0.2, 0.5);
+    const CLOSED_COLOR: Color = Color::rgba(0.3, 0.3, 0.8, 0.5);
+    const CURRENT_COLOR: Color = Color::rgba(1.0, 0.9, 0.1, 0.8);
+    const PATH_COLOR: Color = Color::rgba(0.1, 0.9, 0.9, 0.8);
+
+    fn new_search(maze: &MazeResource, algorithm: SearchAlgorithm) -> Option<AStarSearch> {
+        let start = maze.player_spawn_coord()?;
+        let end = *maze.enemy_spawn_coords().first()?;
+        Some(AStarSearch::with_algorithm(maze, start, end, algorithm))
+    }
+
+    fn input_system(
+        maze: Res<MazeResource>,
+        input: Res<Input<KeyCode>>,
+        mut visualizer: ResMut<SearchVisualizer>,
+    ) {
+        if input.just_pressed(KeyCode::V) {
+            visualizer.enabled = !visualizer.enabled;
+            visualizer.search = None;
+            visualizer.running = false;
+            visualizer.redraw = true;
+            log::info!("search visualizer enabled: {}", visualizer.enabled);
+        }
+        if !visualizer.enabled {
+            return;
+        }
+
+        if input.just_pressed(KeyCode::Tab) {
+            let i = SearchAlgorithm::ALL
+                .iter()
+                .position(|&algorithm| algorithm == visualizer.algorithm)
+                .unwrap_or(0);
+            visualizer.algorithm = SearchAlgorithm::ALL[(i + 1) % SearchAlgorithm::ALL.len()];
+            visualizer.search = None;
+            log::info!("search visualizer algorithm: {:?}", visualizer.algorithm);
+        }
+
+        // the search went through the cells before the edit, start over
+        let edited = maze.changed_coords.len() != visualizer.seen_changes;
+        visualizer.seen_changes = maze.changed_coords.len();
+
+        if input.just_pressed(KeyCode::X) || edited || visualizer.search.is_none() {
+            visualizer.search = Self::new_search(&maze, visualizer.algorithm);
+            visualizer.running = false;
+            visualizer.redraw = true;
+        }
+
+        if input.just_pressed(KeyCode::R) {
+            visualizer.running = !visualizer.running;
+        }
+
+        let step = visualizer.running || input.just_pressed(KeyCode::N);
+        let visualizer = &mut *visualizer;
+        if let (true, Some(search)) = (step, visualizer.search.as_mut()) {
+            if *search.step(&maze) != SearchStatus::InProgress {
+                visualizer.running = false;
+                log::info!(
+                    "search finished after {} expansions: {:?}",
+                    search.expansions,
+                    search.status()
+                );
+            }
+            visualizer.redraw = true;
+        }
+    }
+
+    fn draw_system(
+        mut cmd: Commands,
+        maze: Res<MazeResource>,
+        mut visualizer: ResMut<SearchVisualizer>,
+        overlays: Query<Entity, With<SearchOverlay>>,
+    ) {
+        if !visualizer.redraw {
+            return;
+        }
+        visualizer.redraw = false;
+
+        for entity in overlays.iter() {
+            cmd.entity(entity).despawn();
+        }
+
+        let search = match (&visualizer.search, visualizer.enabled) {
+            (Some(search), true) => search,
+            _ => return,
+        };
+
+        let mut spawn_overlay = |coord: Coord, color: Color, z: f32| {
+            let pos = maze.screen_pos_from_maze_coord(coord);
+            cmd.spawn_bundle(SpriteBundle {
+                transform: Transform::from_xyz(pos.x, pos.y, z),
+                sprite: maze.square_sprite(color),
+                ..Default::default()
+            })
+            .insert(SearchOverlay);
+        };
+
+        for coord in search.closed_cells() {
+            spawn_overlay(coord, Self::CLOSED_COLOR, 1.);
+        }
+        for coord in search.open_cells() {
+            spawn_overlay(coord, Self::OPEN_COLOR, 1.);
+        }
+        if let SearchStatus::Found(path) = search.status() {
+            for &coord in path {
+                spawn_overlay(coord, Self::PATH_COLOR, 2.);
+            }
+        }
+        if let Some(current) = search.current() {
+            spawn_overlay(current, Self::CURRENT_COLOR, 3.);
+        }
+    }
+
+    fn disable_system(
+        mut cmd: Commands,
+        mut visualizer: ResMut<SearchVisualizer>,
+        overlays: Query<Entity, With<SearchOverlay>>,
+    ) {
+        *visualizer = SearchVisualizer::default();
+        for entity in overlays.iter() {
+            cmd.entity(entity).despawn();
+        }
+    }
+}
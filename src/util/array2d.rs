@@ -7,7 +7,12 @@ type Coord = (usize, usize);
 const NEW_LINE_CHAR: char = '\n';
 const NEW_LINE_CHAR_LEN: usize = 1;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "serde_checks::UncheckedArray2D<T>")
+)]
 pub struct Array2D<T: Clone> {
     data: Vec<T>,
     pub height: usize,
@@ -32,6 +37,44 @@ impl<T: Clone + PartialEq> PartialEq for Array2D<T> {
 
 impl<T: Clone + PartialEq> Eq for Array2D<T> {}
 
+#[cfg(feature = "serde")]
+mod serde_checks {
+    use super::*;
+
+    /// an `Array2D` as it was read, its dimensions may not match its data
+    #[derive(serde::Deserialize)]
+    pub struct UncheckedArray2D<T> {
+        data: Vec<T>,
+        height: usize,
+        width: usize,
+    }
+
+    impl<T: Clone> TryFrom<UncheckedArray2D<T>> for Array2D<T> {
+        type Error = String;
+
+        fn try_from(unchecked: UncheckedArray2D<T>) -> Result<Self, Self::Error> {
+            let UncheckedArray2D {
+                data,
+                height,
+                width,
+            } = unchecked;
+            if width.checked_mul(height) != Some(data.len()) {
+                return Err(format!(
+                    "{} items don't fill a {}x{} array",
+                    data.len(),
+                    width,
+                    height
+                ));
+            }
+            Ok(Self {
+                data,
+                height,
+                width,
+            })
+        }
+    }
+}
+
 impl<T: Clone> Array2D<T> {
     // returns an iterator of the data in the order it is stored internally
     pub fn iter_data(&self) -> std::slice::Iter<T> {
@@ -134,6 +177,438 @@ impl<T: Clone> Array2D<T> {
     }
 }
 
+pub use access::*;
+mod access {
+    use super::*;
+    use crate::util::Direction;
+    use std::ops::{Index, IndexMut};
+
+    // the 8 cells around a cell, the 4 orthogonal ones first in the order of `Direction::ALL`
+    const OFFSETS_8: [(isize, isize); 8] = [
+        (0, 1),
+        (0, -1),
+        (-1, 0),
+        (1, 0),
+        (-1, 1),
+        (1, 1),
+        (-1, -1),
+        (1, -1),
+    ];
+
+    impl<T: Clone> Array2D<T> {
+        pub fn in_bounds(&self, (x, y): Coord) -> bool {
+            x < self.width && y < self.height
+        }
+
+        /// None out of bounds, unlike `get`
+        pub fn try_get(&self, coord: Coord) -> Option<&T> {
+            self.in_bounds(coord).then(|| self.get(coord))
+        }
+
+        pub fn try_get_mut(&mut self, coord: Coord) -> Option<&mut T> {
+            if self.in_bounds(coord) {
+                Some(&mut self[coord])
+            } else {
+                None
+            }
+        }
+
+        /// the coordinates above, below, left and right of the coordinate that are inside the array
+        pub fn neighbours_4(&self, coord: Coord) -> impl Iterator<Item = Coord> {
+            let size = (self.width, self.height);
+            Direction::ALL
+                .into_iter()
+                .filter_map(move |dir| dir.step(coord, size))
+        }
+
+        /// `neighbours_4` and the diagonal ones
+        pub fn neighbours_8(&self, (x, y): Coord) -> impl Iterator<Item = Coord> {
+            let (width, height) = (self.width, self.height);
+            OFFSETS_8.into_iter().filter_map(move |(dx, dy)| {
+                let (x, y) = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+                (x < width && y < height).then_some((x, y))
+            })
+        }
+
+        pub fn row(&self, y: usize) -> &[T] {
+            assert!(y < self.height, "row {} of {}", y, self.height);
+            &self.data[y * self.width..(y + 1) * self.width]
+        }
+
+        pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+            assert!(y < self.height, "row {} of {}", y, self.height);
+            &mut self.data[y * self.width..(y + 1) * self.width]
+        }
+
+        /// columns aren't stored next to each other, so an iterator from bottom to top instead of a slice
+        pub fn col(&self, x: usize) -> impl Iterator<Item = &T> + '_ {
+            assert!(x < self.width, "column {} of {}", x, self.width);
+            (0..self.height).map(move |y| self.get((x, y)))
+        }
+
+        /// the rectangle with its bottom left corner at `origin`, which has to be inside the array
+        pub fn view(&self, origin: Coord, width: usize, height: usize) -> Array2DView<'_, T> {
+            assert!(
+                origin.0 + width <= self.width && origin.1 + height <= self.height,
+                "can't view {}x{} at {:?} of {}x{}",
+                width,
+                height,
+                origin,
+                self.width,
+                self.height
+            );
+            Array2DView {
+                array: self,
+                origin,
+                width,
+                height,
+            }
+        }
+
+        pub fn map<U: Clone>(&self, f: impl FnMut(&T) -> U) -> Array2D<U> {
+            Array2D {
+                data: self.data.iter().map(f).collect(),
+                height: self.height,
+                width: self.width,
+            }
+        }
+
+        /// combines the values at the same coordinates of two arrays of the same size
+        pub fn zip<U: Clone, V: Clone>(
+            &self,
+            other: &Array2D<U>,
+            mut f: impl FnMut(&T, &U) -> V,
+        ) -> Array2D<V> {
+            assert_eq!(
+                (self.width, self.height),
+                (other.width, other.height),
+                "can only zip arrays of the same size"
+            );
+            Array2D {
+                data: self
+                    .data
+                    .iter()
+                    .zip(&other.data)
+                    .map(|(a, b)| f(a, b))
+                    .collect(),
+                height: self.height,
+                width: self.width,
+            }
+        }
+    }
+
+    impl<T: Clone> Index<Coord> for Array2D<T> {
+        type Output = T;
+
+        fn index(&self, coord: Coord) -> &T {
+            self.get(coord)
+        }
+    }
+
+    impl<T: Clone> IndexMut<Coord> for Array2D<T> {
+        fn index_mut(&mut self, (x, y): Coord) -> &mut T {
+            debug_assert!(x < self.width);
+            debug_assert!(y < self.height);
+
+            let index = self.get_index((x, y));
+            &mut self.data[index]
+        }
+    }
+
+    /// A rectangle of an `Array2D`, borrowed instead of copied. Coordinates are relative to its bottom left
+    ///  corner.
+    #[derive(Debug)]
+    pub struct Array2DView<'a, T: Clone> {
+        array: &'a Array2D<T>,
+        origin: Coord,
+        pub width: usize,
+        pub height: usize,
+    }
+
+    impl<'a, T: Clone> Array2DView<'a, T> {
+        pub fn in_bounds(&self, (x, y): Coord) -> bool {
+            x < self.width && y < self.height
+        }
+
+        pub fn get(&self, (x, y): Coord) -> &'a T {
+            debug_assert!(self.in_bounds((x, y)));
+            self.array.get((self.origin.0 + x, self.origin.1 + y))
+        }
+
+        pub fn try_get(&self, coord: Coord) -> Option<&'a T> {
+            self.in_bounds(coord).then(|| self.get(coord))
+        }
+
+        pub fn row(&self, y: usize) -> &'a [T] {
+            assert!(y < self.height, "row {} of {}", y, self.height);
+            &self.array.row(self.origin.1 + y)[self.origin.0..self.origin.0 + self.width]
+        }
+
+        /// in order (0, 0), (1, 0), (2, 0), (0, 1)... like `Array2D::iter_rows_first_enumerated`
+        pub fn iter_rows_first_enumerated(&self) -> impl Iterator<Item = (Coord, &'a T)> + 'a {
+            let (array, origin, width) = (self.array, self.origin, self.width);
+            (0..self.height).flat_map(move |y| {
+                (0..width).map(move |x| ((x, y), array.get((origin.0 + x, origin.1 + y))))
+            })
+        }
+
+        pub fn to_array(&self) -> Array2D<T> {
+            Array2D::from_fn(self.width, self.height, |coord| self.get(coord).clone())
+        }
+    }
+}
+
+pub use regions::*;
+mod regions {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Connected regions of an `Array2D`, cells are connected to their 4 neighbours.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Components {
+        /// the region of every cell counting from 1, 0 for the cells that aren't part of any
+        pub labels: Array2D<u32>,
+        /// the number of cells in each region, the size of region `label` is at `label - 1`
+        pub sizes: Vec<usize>,
+    }
+
+    impl Components {
+        pub fn count(&self) -> usize {
+            self.sizes.len()
+        }
+
+        pub fn size(&self, label: u32) -> usize {
+            match label {
+                0 => 0,
+                _ => self.sizes[label as usize - 1],
+            }
+        }
+
+        /// label of the region with the most cells, the first one on a tie
+        pub fn largest(&self) -> Option<u32> {
+            let mut largest: Option<(u32, usize)> = None;
+            for (i, &size) in self.sizes.iter().enumerate() {
+                if largest.is_none_or(|(_, largest_size)| size > largest_size) {
+                    largest = Some((i as u32 + 1, size));
+                }
+            }
+            largest.map(|(label, _)| label)
+        }
+    }
+
+    impl<T: Clone> Array2D<T> {
+        /// Every cell connected to `start` through cells matching the predicate, in the order they're reached.
+        /// Empty if `start` doesn't match.
+        pub fn flood_fill(&self, start: Coord, mut matches: impl FnMut(&T) -> bool) -> Vec<Coord> {
+            let mut seen = Array2D::new(self.width, self.height, false);
+            let mut region = Vec::new();
+            self.fill_from(start, &mut matches, &mut seen, &mut region);
+            region
+        }
+
+        /// sets the region around `start` of cells with the same value as it to `value`, returns the cells set
+        pub fn fill_region(&mut self, start: Coord, value: T) -> Vec<Coord>
+        where
+            T: PartialEq,
+        {
+            let old = self.get(start).clone();
+            let region = self.flood_fill(start, |other| *other == old);
+            for &coord in &region {
+                self[coord] = value.clone();
+            }
+            region
+        }
+
+        /// labels the regions of connected cells matching the predicate, in the order of
+        ///  `iter_rows_first_enumerated`
+        pub fn label_components(&self, mut matches: impl FnMut(&T) -> bool) -> Components {
+            let mut seen = Array2D::new(self.width, self.height, false);
+            let mut labels = Array2D::new(self.width, self.height, 0);
+            let mut sizes = Vec::new();
+
+            let mut region = Vec::new();
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    region.clear();
+                    self.fill_from((x, y), &mut matches, &mut seen, &mut region);
+                    if region.is_empty() {
+                        continue;
+                    }
+
+                    sizes.push(region.len());
+                    for &coord in &region {
+                        labels[coord] = sizes.len() as u32;
+                    }
+                }
+            }
+
+            Components { labels, sizes }
+        }
+
+        // breadth first from `start` over the cells not seen yet
+        fn fill_from(
+            &self,
+            start: Coord,
+            matches: &mut impl FnMut(&T) -> bool,
+            seen: &mut Array2D<bool>,
+            region: &mut Vec<Coord>,
+        ) {
+            if seen[start] || !matches(self.get(start)) {
+                return;
+            }
+            seen[start] = true;
+
+            let mut queue = VecDeque::from([start]);
+            while let Some(cell) = queue.pop_front() {
+                region.push(cell);
+                for neighbour in self.neighbours_4(cell) {
+                    if !seen[neighbour] && matches(self.get(neighbour)) {
+                        seen[neighbour] = true;
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+        }
+    }
+}
+
+mod distance {
+    use super::*;
+    use std::cmp::Reverse;
+    use std::collections::{BinaryHeap, VecDeque};
+
+    impl<T: Clone> Array2D<T> {
+        /// Steps from the nearest of the sources to every cell, moving between 4 neighbours through passable
+        ///  cells. None for the cells that can't be reached. The sources themselves are always 0.
+        pub fn bfs_distances(
+            &self,
+            sources: impl IntoIterator<Item = Coord>,
+            mut passable: impl FnMut(&T) -> bool,
+        ) -> Array2D<Option<u32>> {
+            let mut distances = Array2D::new(self.width, self.height, None);
+            let mut queue = VecDeque::new();
+            for source in sources {
+                if distances[source].is_none() {
+                    distances[source] = Some(0);
+                    queue.push_back(source);
+                }
+            }
+
+            while let Some(cell) = queue.pop_front() {
+                let distance = distances[cell].unwrap();
+                for neighbour in self.neighbours_4(cell) {
+                    if distances[neighbour].is_none() && passable(self.get(neighbour)) {
+                        distances[neighbour] = Some(distance + 1);
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+            distances
+        }
+
+        /// Like `bfs_distances`, but stepping onto a cell costs whatever `cost` returns for it, None if it
+        ///  can't be stepped on.
+        pub fn dijkstra_distances(
+            &self,
+            sources: impl IntoIterator<Item = Coord>,
+            mut cost: impl FnMut(Coord, &T) -> Option<u32>,
+        ) -> Array2D<Option<u32>> {
+            let mut distances = Array2D::new(self.width, self.height, None);
+            // reversed to make the BinaryHeap a min-heap
+            let mut open_set = BinaryHeap::new();
+            for source in sources {
+                distances[source] = Some(0);
+                open_set.push(Reverse((0, source)));
+            }
+
+            while let Some(Reverse((distance, cell))) = open_set.pop() {
+                // already reached through a shorter way
+                if distances[cell].is_some_and(|best| best < distance) {
+                    continue;
+                }
+                for neighbour in self.neighbours_4(cell) {
+                    let next = match cost(neighbour, self.get(neighbour)) {
+                        Some(step) => distance + step,
+                        None => continue,
+                    };
+                    if distances[neighbour].is_none_or(|best| next < best) {
+                        distances[neighbour] = Some(next);
+                        open_set.push(Reverse((next, neighbour)));
+                    }
+                }
+            }
+            distances
+        }
+
+        /// Euclidean distance from every cell to the centre of the nearest wall cell, 0 on the walls and
+        ///  infinite if there are none. Outside the array doesn't count as wall.
+        pub fn distance_to_nearest(&self, mut is_wall: impl FnMut(&T) -> bool) -> Array2D<f32> {
+            // squared distances along each column first, then along each row to the columns
+            let mut squared = Array2D::new(self.width, self.height, f64::INFINITY);
+            for x in 0..self.width {
+                let column: Vec<f64> = (0..self.height)
+                    .map(|y| match is_wall(self.get((x, y))) {
+                        true => 0.,
+                        false => f64::INFINITY,
+                    })
+                    .collect();
+                for (y, distance) in lower_envelope(&column).into_iter().enumerate() {
+                    squared[(x, y)] = distance;
+                }
+            }
+
+            let mut distances = Array2D::new(self.width, self.height, f32::INFINITY);
+            for y in 0..self.height {
+                let row: Vec<f64> = (0..self.width).map(|x| squared[(x, y)]).collect();
+                for (x, distance) in lower_envelope(&row).into_iter().enumerate() {
+                    distances[(x, y)] = distance.sqrt() as f32;
+                }
+            }
+            distances
+        }
+    }
+
+    /// min over q of (p - q)² + f(q) for every p, the lower envelope of the parabolas rooted at each f(q)
+    ///  (Felzenszwalb & Huttenlocher)
+    fn lower_envelope(f: &[f64]) -> Vec<f64> {
+        let parabola = |q: usize| f[q] + (q * q) as f64;
+
+        // vertices of the parabolas in the envelope, and where along the line each one takes over
+        let mut vertices: Vec<usize> = Vec::new();
+        let mut starts: Vec<f64> = Vec::new();
+        for q in (0..f.len()).filter(|&q| f[q].is_finite()) {
+            while let Some(&v) = vertices.last() {
+                let start = (parabola(q) - parabola(v)) / (2 * (q - v)) as f64;
+                if start > *starts.last().unwrap() {
+                    vertices.push(q);
+                    starts.push(start);
+                    break;
+                }
+                vertices.pop();
+                starts.pop();
+            }
+            if vertices.is_empty() {
+                vertices.push(q);
+                starts.push(f64::NEG_INFINITY);
+            }
+        }
+
+        let mut k = 0;
+        (0..f.len())
+            .map(|p| {
+                if vertices.is_empty() {
+                    return f64::INFINITY;
+                }
+                while k + 1 < starts.len() && starts[k + 1] < p as f64 {
+                    k += 1;
+                }
+                let v = vertices[k];
+                (p as f64 - v as f64).powi(2) + f[v]
+            })
+            .collect()
+    }
+}
+
 pub use to_string::*;
 mod to_string {
     use super::*;
@@ -175,42 +650,431 @@ pub use from_string::*;
 mod from_string {
     use super::*;
 
-    fn find_width(arr_str: &str) -> usize {
-        let (first_line, _) = arr_str
-            .split_once(NEW_LINE_CHAR)
-            .expect("couldn't split string");
-        first_line.chars().count()
+    pub trait FromChar: Sized {
+        /// The item a character stands for when reading an array from a string, the opposite of
+        ///  `CharRepresentation`. None if it doesn't stand for any.
+        fn from_char(c: char) -> Option<Self>;
+    }
+    impl FromChar for char {
+        fn from_char(c: char) -> Option<Self> {
+            Some(c)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ParseErrorKind {
+        /// there isn't a single non empty line
+        Empty,
+        UnknownChar(char),
+        /// a row with another length than the first one
+        RaggedRow {
+            len: usize,
+            expected: usize,
+        },
     }
 
-    fn find_height(arr_str: &str) -> usize {
-        arr_str
-            .split(NEW_LINE_CHAR)
-            .filter(|&line| line.is_empty() == false) // ignore any empty lines
-            .count()
+    /// Where and why a string couldn't be read as an array, the line and column count from 1.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ParseError {
+        pub line: usize,
+        pub column: usize,
+        pub kind: ParseErrorKind,
+    }
+
+    impl Display for ParseError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "line {}, column {}: ", self.line, self.column)?;
+            match self.kind {
+                ParseErrorKind::Empty => write!(f, "there is nothing to read"),
+                ParseErrorKind::UnknownChar(c) => write!(f, "unknown character {:?}", c),
+                ParseErrorKind::RaggedRow { len, expected } => write!(
+                    f,
+                    "the row is {} long, expected {} like the first row",
+                    len, expected
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for ParseError {}
+
+    impl<T: Clone + FromChar> Array2D<T> {
+        /// Reads an array written as one row per line, like `to_string` writes it. Empty lines are skipped.
+        pub fn parse(text: &str) -> Result<Self, ParseError> {
+            let mut data = Vec::new();
+            let mut width = None;
+            let mut height = 0;
+
+            for (i, line) in text.lines().enumerate() {
+                if line.is_empty() {
+                    continue;
+                }
+
+                let mut len = 0;
+                for (x, c) in line.chars().enumerate() {
+                    let item = T::from_char(c).ok_or(ParseError {
+                        line: i + 1,
+                        column: x + 1,
+                        kind: ParseErrorKind::UnknownChar(c),
+                    })?;
+                    data.push(item);
+                    len += 1;
+                }
+
+                let expected = *width.get_or_insert(len);
+                if len != expected {
+                    return Err(ParseError {
+                        line: i + 1,
+                        column: len.min(expected) + 1,
+                        kind: ParseErrorKind::RaggedRow { len, expected },
+                    });
+                }
+                height += 1;
+            }
+
+            match width {
+                Some(width) => Ok(Self {
+                    data,
+                    height,
+                    width,
+                }),
+                None => Err(ParseError {
+                    line: 1,
+                    column: 1,
+                    kind: ParseErrorKind::Empty,
+                }),
+            }
+        }
     }
 
     impl From<String> for Array2D<char> {
         fn from(str: String) -> Self {
-            let (width, height) = (find_width(&str), find_height(&str));
+            Self::parse(&str).unwrap_or_else(|err| panic!("couldn't read array: {}", err))
+        }
+    }
+}
 
-            let mut arr = Array2D::new(width, height, char::default());
+mod transform {
+    use super::*;
 
-            for (y, line) in str
-                .lines()
-                .into_iter()
-                .filter(|&line| line.is_empty() == false)
-                .enumerate()
-            {
-                for (x, symbol) in line.chars().enumerate() {
-                    arr.set((x, y), symbol);
-                }
+    /// Transforms return a new array. Rotations are clockwise as the grid is shown in the game, where y goes
+    ///  up.
+    impl<T: Clone> Array2D<T> {
+        pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(Coord) -> T) -> Self {
+            let data = (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(&mut f)
+                .collect();
+
+            Self {
+                data,
+                height,
+                width,
             }
+        }
+
+        pub fn rotate_90(&self) -> Self {
+            Self::from_fn(self.height, self.width, |(x, y)| {
+                self.get((self.width - 1 - y, x)).clone()
+            })
+        }
 
-            arr
+        pub fn rotate_180(&self) -> Self {
+            Self::from_fn(self.width, self.height, |(x, y)| {
+                self.get((self.width - 1 - x, self.height - 1 - y)).clone()
+            })
+        }
+
+        pub fn rotate_270(&self) -> Self {
+            Self::from_fn(self.height, self.width, |(x, y)| {
+                self.get((y, self.height - 1 - x)).clone()
+            })
+        }
+
+        /// mirrored left to right
+        pub fn flip_horizontal(&self) -> Self {
+            Self::from_fn(self.width, self.height, |(x, y)| {
+                self.get((self.width - 1 - x, y)).clone()
+            })
+        }
+
+        /// mirrored top to bottom
+        pub fn flip_vertical(&self) -> Self {
+            Self::from_fn(self.width, self.height, |(x, y)| {
+                self.get((x, self.height - 1 - y)).clone()
+            })
+        }
+
+        /// x and y swapped
+        pub fn transpose(&self) -> Self {
+            Self::from_fn(self.height, self.width, |(x, y)| self.get((y, x)).clone())
+        }
+
+        /// the rectangle with its bottom left corner at `origin`, which has to be inside the array
+        pub fn crop(&self, origin: Coord, width: usize, height: usize) -> Self {
+            self.view(origin, width, height).to_array()
+        }
+
+        /// cut or filled up with `fill` on the right and top
+        pub fn resize(&self, width: usize, height: usize, fill: T) -> Self {
+            Self::from_fn(width, height, |(x, y)| {
+                if x < self.width && y < self.height {
+                    self.get((x, y)).clone()
+                } else {
+                    fill.clone()
+                }
+            })
+        }
+
+        /// a border of `fill`, the given number of cells wide on each side
+        pub fn pad(&self, left: usize, right: usize, bottom: usize, top: usize, fill: T) -> Self {
+            Self::from_fn(
+                left + self.width + right,
+                bottom + self.height + top,
+                |(x, y)| {
+                    let inside = (left..left + self.width).contains(&x)
+                        && (bottom..bottom + self.height).contains(&y);
+                    if inside {
+                        self.get((x - left, y - bottom)).clone()
+                    } else {
+                        fill.clone()
+                    }
+                },
+            )
+        }
+
+        /// `other` to the right of this one, the lower of the two filled up with `fill` on top
+        pub fn stitch_horizontal(&self, other: &Self, fill: T) -> Self {
+            let height = self.height.max(other.height);
+            Self::from_fn(self.width + other.width, height, |(x, y)| {
+                let (grid, x) = if x < self.width {
+                    (self, x)
+                } else {
+                    (other, x - self.width)
+                };
+                if y < grid.height {
+                    grid.get((x, y)).clone()
+                } else {
+                    fill.clone()
+                }
+            })
+        }
+
+        /// `other` on top of this one, the narrower of the two filled up with `fill` on the right
+        pub fn stitch_vertical(&self, other: &Self, fill: T) -> Self {
+            self.transpose()
+                .stitch_horizontal(&other.transpose(), fill)
+                .transpose()
         }
     }
 }
 
+#[test]
+fn test_transforms() {
+    // y goes up on screen, so the last row of the string is the top of the grid
+    let arr = Array2D::from("abc\ndef\n".to_string());
+
+    assert_eq!(arr.rotate_90().to_string(), "cf\nbe\nad\n");
+    assert_eq!(arr.rotate_180().to_string(), "fed\ncba\n");
+    assert_eq!(arr.rotate_270().to_string(), "da\neb\nfc\n");
+    assert_eq!(arr.rotate_90().rotate_270(), arr);
+    assert_eq!(arr.rotate_90().rotate_90(), arr.rotate_180());
+
+    assert_eq!(arr.flip_horizontal().to_string(), "cba\nfed\n");
+    assert_eq!(arr.flip_vertical().to_string(), "def\nabc\n");
+    assert_eq!(arr.transpose().to_string(), "ad\nbe\ncf\n");
+
+    assert_eq!(arr.crop((1, 1), 2, 1).to_string(), "ef\n");
+    assert_eq!(arr.resize(2, 3, '.').to_string(), "ab\nde\n..\n");
+    assert_eq!(arr.pad(1, 0, 0, 1, '.').to_string(), ".abc\n.def\n....\n");
+
+    let tall = Array2D::from("x\ny\nz\n".to_string());
+    assert_eq!(
+        arr.stitch_horizontal(&tall, '.').to_string(),
+        "abcx\ndefy\n...z\n"
+    );
+    assert_eq!(
+        arr.stitch_vertical(&tall, '.').to_string(),
+        "abc\ndef\nx..\ny..\nz..\n"
+    );
+}
+
+#[test]
+fn test_access() {
+    // y goes up on screen, so the last row of the string is the top of the grid
+    let mut arr = Array2D::from("abc\ndef\nghi\n".to_string());
+
+    assert_eq!(arr[(1, 2)], 'h');
+    arr[(1, 2)] = 'x';
+    assert_eq!(arr.try_get((1, 2)), Some(&'x'));
+    assert_eq!(arr.try_get((3, 0)), None);
+    assert_eq!(arr.try_get((0, 3)), None);
+
+    let neighbours: Vec<Coord> = arr.neighbours_4((0, 0)).collect();
+    assert_eq!(neighbours, vec![(0, 1), (1, 0)]);
+    assert_eq!(arr.neighbours_4((1, 1)).count(), 4);
+    let neighbours: Vec<Coord> = arr.neighbours_8((0, 2)).collect();
+    assert_eq!(neighbours, vec![(0, 1), (1, 2), (1, 1)]);
+    assert_eq!(arr.neighbours_8((1, 1)).count(), 8);
+
+    assert_eq!(arr.row(1), &['d', 'e', 'f']);
+    arr.row_mut(1)[0] = 'y';
+    assert_eq!(arr.col(0).collect::<String>(), "ayg");
+
+    let view = arr.view((1, 1), 2, 2);
+    assert_eq!(*view.get((0, 0)), 'e');
+    assert_eq!(view.try_get((2, 0)), None);
+    assert_eq!(view.row(1), &['x', 'i']);
+    assert_eq!(view.to_array().to_string(), "ef\nxi\n");
+    assert_eq!(view.iter_rows_first_enumerated().count(), 4);
+
+    let upper = arr.map(|c| c.to_ascii_uppercase());
+    assert_eq!(upper.to_string(), "ABC\nYEF\nGXI\n");
+    let same = arr.zip(&upper, |a, b| a.to_ascii_uppercase() == *b);
+    assert!(same.iter_data().all(|&same| same));
+}
+
+#[test]
+fn test_regions() {
+    let mut arr = Array2D::from(
+        "\
+        11.22\n\
+        1...2\n\
+        ..33.\n"
+            .to_string(),
+    );
+
+    let mut region = arr.flood_fill((0, 0), |&c| c == '1');
+    region.sort();
+    assert_eq!(region, vec![(0, 0), (0, 1), (1, 0)]);
+    assert!(arr.flood_fill((2, 0), |&c| c == '1').is_empty());
+
+    // works for anything, here the digits
+    let components = arr
+        .map(|c| c.to_digit(10))
+        .label_components(Option::is_some);
+    assert_eq!(components.sizes, vec![3, 3, 2]);
+    assert_eq!(
+        components.labels.map(|&label| label as usize).to_string(),
+        "11022\n10002\n00330\n"
+    );
+    assert_eq!(components.largest(), Some(1));
+    assert_eq!(components.size(0), 0);
+
+    // the free cells, the one on the right is cut off
+    let free = arr.label_components(|&c| c == '.');
+    assert_eq!(free.count(), 2);
+    assert_eq!(free.size(free.labels[(4, 2)]), 1);
+
+    assert_eq!(arr.fill_region((1, 1), '#').len(), 6);
+    assert_eq!(arr.to_string(), "11#22\n1###2\n##33.\n");
+}
+
+#[test]
+fn test_distances() {
+    let arr = Array2D::from(
+        "\
+        ..#..\n\
+        .~#..\n\
+        .....\n"
+            .to_string(),
+    );
+
+    let steps = arr.bfs_distances([(0, 0), (4, 2)], |&c| c != '#');
+    assert_eq!(steps[(1, 1)], Some(2));
+    assert_eq!(steps[(3, 0)], Some(3));
+    assert_eq!(steps[(2, 0)], None);
+
+    // wading through ~ takes 5 steps
+    let cost = arr.dijkstra_distances([(0, 0)], |_, &c| match c {
+        '#' => None,
+        '~' => Some(5),
+        _ => Some(1),
+    });
+    assert_eq!(cost[(1, 1)], Some(6));
+    assert_eq!(cost[(1, 2)], Some(3));
+    assert_eq!(cost[(4, 0)], Some(8));
+    assert_eq!(cost[(2, 1)], None);
+
+    let walls = arr.distance_to_nearest(|&c| c == '#');
+    assert_eq!(walls[(2, 0)], 0.);
+    assert_eq!(walls[(0, 0)], 2.);
+    assert_eq!(walls[(0, 2)], 5f32.sqrt());
+    assert_eq!(walls[(4, 2)], 5f32.sqrt());
+    assert!(arr
+        .distance_to_nearest(|&c| c == '?')
+        .iter_data()
+        .all(|d| d.is_infinite()));
+}
+
+#[test]
+fn test_parse() {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Cell {
+        Wall,
+        Floor,
+    }
+    impl FromChar for Cell {
+        fn from_char(c: char) -> Option<Self> {
+            match c {
+                '#' => Some(Cell::Wall),
+                '.' => Some(Cell::Floor),
+                _ => None,
+            }
+        }
+    }
+
+    let arr = Array2D::<Cell>::parse("#..#\n\n##.#\n").unwrap();
+    assert_eq!((arr.width, arr.height), (4, 2));
+    assert_eq!(arr[(2, 1)], Cell::Floor);
+    assert_eq!(arr[(0, 1)], Cell::Wall);
+
+    // a single line without a new line at the end
+    let arr = Array2D::<char>::parse("abc").unwrap();
+    assert_eq!(arr.to_string(), "abc\n");
+
+    let err = Array2D::<Cell>::parse("#..#\n\n#?.#\n").unwrap_err();
+    assert_eq!((err.line, err.column), (3, 2));
+    assert_eq!(err.kind, ParseErrorKind::UnknownChar('?'));
+
+    let err = Array2D::<Cell>::parse("#..#\n##\n").unwrap_err();
+    assert_eq!((err.line, err.column), (2, 3));
+    assert_eq!(
+        err.kind,
+        ParseErrorKind::RaggedRow {
+            len: 2,
+            expected: 4
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "line 2, column 3: the row is 2 long, expected 4 like the first row"
+    );
+
+    assert_eq!(
+        Array2D::<char>::parse("\n\n").unwrap_err().kind,
+        ParseErrorKind::Empty
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_checks_dimensions() {
+    let arr: Array2D<u8> =
+        serde_json::from_str(r#"{"data":[1,2,3,4],"width":2,"height":2}"#).unwrap();
+    assert_eq!(arr[(1, 1)], 4);
+
+    let err =
+        serde_json::from_str::<Array2D<u8>>(r#"{"data":[],"width":2,"height":2}"#).unwrap_err();
+    assert!(err.to_string().contains("0 items don't fill a 2x2 array"));
+    assert!(ron::from_str::<Array2D<u8>>("(data: [1, 2, 3], width: 2, height: 2)").is_err());
+
+    // 2^32 * 2^32 wraps to 0 on 64 bit targets
+    let huge = 1u64 << 32;
+    let overflowing = format!(r#"{{"data":[],"width":{},"height":{}}}"#, huge, huge);
+    assert!(serde_json::from_str::<Array2D<u8>>(&overflowing).is_err());
+}
+
 #[test]
 fn test_2d_arr() {
     let mut arr = Array2D::new(10, 4, '|');
@@ -0,0 +1,93 @@
+use bevy::math::Vec2;
+
+type Coord = (usize, usize);
+
+/// One of the four directions an agent can move in on the maze grid.
+/// Up is +y, matching how the maze is laid out on screen (origin in the bottom left).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    pub fn offset(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    /// turned 90 degrees to the right
+    pub fn clockwise(self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    /// turned 90 degrees to the left
+    pub fn counter_clockwise(self) -> Self {
+        self.clockwise().opposite()
+    }
+
+    /// the coordinate one step in this direction, or None if it would leave a grid of the given size
+    pub fn step(self, (x, y): Coord, (width, height): (usize, usize)) -> Option<Coord> {
+        let (dx, dy) = self.offset();
+        let x = x.checked_add_signed(dx)?;
+        let y = y.checked_add_signed(dy)?;
+
+        if x < width && y < height {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
+    /// unit vector in screen space
+    pub fn to_vec2(self) -> Vec2 {
+        let (dx, dy) = self.offset();
+        Vec2::new(dx as f32, dy as f32)
+    }
+
+    /// the direction closest to the given vector, or None for a zero vector
+    pub fn from_vec2(v: Vec2) -> Option<Self> {
+        if v == Vec2::ZERO {
+            None
+        } else if v.x.abs() > v.y.abs() {
+            Some(if v.x > 0. {
+                Direction::Right
+            } else {
+                Direction::Left
+            })
+        } else {
+            Some(if v.y > 0. {
+                Direction::Up
+            } else {
+                Direction::Down
+            })
+        }
+    }
+}
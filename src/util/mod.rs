@@ -1,9 +1,11 @@
 pub mod array2d;
+pub mod direction;
 pub mod pathfinding;
 
 pub mod file_io;
 
 pub use array2d::Array2D;
+pub use direction::Direction;
 
 use bevy::math::{Vec2, Vec3};
 
@@ -0,0 +1,314 @@
+use super::walkable_neighbours;
+use crate::maze::{Coord, Maze};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::Hash;
+
+#[derive(Clone)]
+struct Node {
+    coord: Coord,
+    // distance between current and start
+    g_cost: u32,
+    // estimated distance from current node to end node
+    h_cost: u32,
+}
+
+impl Node {
+    fn h_cost(from: Coord, end: Coord) -> u32 {
+        // manhattan distance, never overestimates as every step costs at least 1
+        (from.0.abs_diff(end.0) + from.1.abs_diff(end.1)) as u32
+    }
+
+    fn f_cost(&self) -> u32 {
+        self.g_cost + self.h_cost
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialEq<Self> for Node {
+    // the same keys as the ordering
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Ord for Node {
+    // reversed, so that the BinaryHeap (a max-heap) pops the lowest f cost first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .f_cost()
+            .cmp(&self.f_cost())
+            // prefer nodes closer to the end on ties
+            .then_with(|| other.h_cost.cmp(&self.h_cost))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Shortest path from start to end (both included) over the walkable cells of the maze.
+pub fn a_star(maze: &Maze, start: Coord, end: Coord) -> Option<Vec<Coord>> {
+    match AStarSearch::new(maze, start, end).run(maze, usize::MAX) {
+        SearchStatus::Found(path) => Some(path),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchStatus {
+    InProgress,
+    Found(Vec<Coord>),
+    NotFound,
+}
+
+/// What decides the order nodes are expanded in. They only differ in the heuristic.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SearchAlgorithm {
+    AStar,
+    /// no heuristic, expands evenly in every direction
+    Dijkstra,
+    /// only the heuristic matters, the cost so far just breaks ties. Fast but the path isn't always the shortest
+    GreedyBestFirst,
+}
+
+impl SearchAlgorithm {
+    pub const ALL: [Self; 3] = [Self::AStar, Self::Dijkstra, Self::GreedyBestFirst];
+
+    fn h_cost(self, from: Coord, end: Coord) -> u32 {
+        match self {
+            Self::AStar => Node::h_cost(from, end),
+            Self::Dijkstra => 0,
+            // outweighs any cost so far on the grids we use
+            Self::GreedyBestFirst => Node::h_cost(from, end).saturating_mul(10_000),
+        }
+    }
+}
+
+/// An A* search that can be paused after any number of expansions and resumed later, e.g. in the next
+///  frame. The maze shouldn't change in between.
+pub struct AStarSearch {
+    pub start: Coord,
+    pub end: Coord,
+    /// nodes expanded so far
+    pub expansions: usize,
+    pub algorithm: SearchAlgorithm,
+    // the node expanded last
+    current: Option<Coord>,
+    open_set: BinaryHeap<Node>,
+    closed_set: HashSet<Coord>,
+    parents: HashMap<Coord, Coord>,
+    g_costs: HashMap<Coord, u32>,
+    status: SearchStatus,
+}
+
+impl AStarSearch {
+    pub fn new(maze: &Maze, start: Coord, end: Coord) -> Self {
+        Self::with_algorithm(maze, start, end, SearchAlgorithm::AStar)
+    }
+
+    pub fn with_algorithm(
+        maze: &Maze,
+        start: Coord,
+        end: Coord,
+        algorithm: SearchAlgorithm,
+    ) -> Self {
+        let mut open_set = BinaryHeap::new();
+        if maze.is_walkable(start) && maze.is_walkable(end) {
+            open_set.push(Node {
+                coord: start,
+                g_cost: 0,
+                h_cost: algorithm.h_cost(start, end),
+            });
+        }
+
+        Self {
+            start,
+            end,
+            expansions: 0,
+            algorithm,
+            current: None,
+            open_set,
+            closed_set: HashSet::new(),
+            parents: HashMap::new(),
+            g_costs: HashMap::from([(start, 0)]),
+            status: SearchStatus::InProgress,
+        }
+    }
+
+    pub fn status(&self) -> &SearchStatus {
+        &self.status
+    }
+
+    pub fn current(&self) -> Option<Coord> {
+        self.current
+    }
+
+    /// cells waiting to be expanded
+    pub fn open_cells(&self) -> impl Iterator<Item = Coord> + '_ {
+        self.open_set
+            .iter()
+            .map(|node| node.coord)
+            .filter(|coord| !self.closed_set.contains(coord))
+    }
+
+    /// cells already expanded
+    pub fn closed_cells(&self) -> impl Iterator<Item = Coord> + '_ {
+        self.closed_set.iter().copied()
+    }
+
+    /// expands a single node
+    pub fn step(&mut self, maze: &Maze) -> &SearchStatus {
+        self.step_with_cost(maze, |_| 1)
+    }
+
+    /// expands a single node, entering a cell costs `cost(cell)` instead of 1
+    fn step_with_cost(&mut self, maze: &Maze, cost: impl Fn(Coord) -> u32) -> &SearchStatus {
+        if self.status != SearchStatus::InProgress {
+            return &self.status;
+        }
+
+        // skip outdated entries, the coord was already expanded with a lower cost
+        let current = loop {
+            match self.open_set.pop() {
+                Some(node) if self.closed_set.contains(&node.coord) => continue,
+                Some(node) => break node,
+                None => {
+                    self.status = SearchStatus::NotFound;
+                    return &self.status;
+                }
+            }
+        };
+
+        self.current = Some(current.coord);
+        if current.coord == self.end {
+            self.status = SearchStatus::Found(reconstruct_path(&self.parents, self.end));
+            return &self.status;
+        }
+
+        self.closed_set.insert(current.coord);
+        self.expansions += 1;
+
+        for neighbour in walkable_neighbours(maze, current.coord) {
+            if self.closed_set.contains(&neighbour) {
+                continue;
+            }
+
+            let g_cost = current.g_cost + cost(neighbour);
+            if self.g_costs.get(&neighbour).is_none_or(|&old| g_cost < old) {
+                self.g_costs.insert(neighbour, g_cost);
+                self.parents.insert(neighbour, current.coord);
+                self.open_set.push(Node {
+                    coord: neighbour,
+                    g_cost,
+                    h_cost: self.algorithm.h_cost(neighbour, self.end),
+                });
+            }
+        }
+
+        &self.status
+    }
+
+    /// steps until the search is done or `max_expansions` more nodes have been expanded
+    pub fn run(&mut self, maze: &Maze, max_expansions: usize) -> SearchStatus {
+        let until = self.expansions.saturating_add(max_expansions);
+        while self.status == SearchStatus::InProgress && self.expansions < until {
+            self.step(maze);
+        }
+        self.status.clone()
+    }
+}
+
+/// A* where entering a cell costs `cost(cell)` instead of 1. Costs must be at least 1.
+pub fn a_star_with_cost(
+    maze: &Maze,
+    start: Coord,
+    end: Coord,
+    cost: impl Fn(Coord) -> u32,
+) -> Option<Vec<Coord>> {
+    let mut search = AStarSearch::new(maze, start, end);
+    loop {
+        match search.step_with_cost(maze, &cost) {
+            SearchStatus::InProgress => continue,
+            SearchStatus::Found(path) => return Some(path.clone()),
+            SearchStatus::NotFound => return None,
+        }
+    }
+}
+
+pub(crate) fn reconstruct_path<K: Copy + Eq + Hash>(parents: &HashMap<K, K>, end: K) -> Vec<K> {
+    let mut path = vec![end];
+    let mut current = end;
+    while let Some(&parent) = parents.get(&current) {
+        path.push(parent);
+        current = parent;
+    }
+    path.reverse();
+    path
+}
+
+#[test]
+fn test_a_star() {
+    use crate::util::Array2D;
+
+    let maze = Maze {
+        grid: Array2D::from(
+            "\
+            #######\n\
+            #.....#\n\
+            #.###.#\n\
+            #.#...#\n\
+            #######\n"
+                .to_string(),
+        ),
+    };
+
+    let path = a_star(&maze, (1, 3), (3, 3)).unwrap();
+    assert_eq!(path.first(), Some(&(1, 3)));
+    assert_eq!(path.last(), Some(&(3, 3)));
+    // around the wall in the middle
+    assert_eq!(path.len(), 11);
+    assert!(path.windows(2).all(|w| Node::h_cost(w[0], w[1]) == 1));
+
+    assert_eq!(a_star(&maze, (1, 3), (2, 3)), None);
+
+    // around the expensive middle row
+    let open = Maze::new_empty(5, 3);
+    let path =
+        a_star_with_cost(&open, (0, 1), (4, 1), |(_, y)| if y == 1 { 10 } else { 1 }).unwrap();
+    assert_eq!(path.len(), 7);
+    assert!(path[1..path.len() - 1].iter().all(|&(_, y)| y != 1));
+}
+
+#[test]
+fn test_search_algorithms() {
+    let maze = Maze::new_empty(20, 20);
+    let (start, end) = ((2, 3), (17, 15));
+
+    let searches = SearchAlgorithm::ALL.map(|algorithm| {
+        let mut search = AStarSearch::with_algorithm(&maze, start, end, algorithm);
+        search.run(&maze, usize::MAX);
+        search
+    });
+    let [a_star, dijkstra, greedy] = &searches;
+
+    // all shortest in an empty maze
+    for search in [&a_star, &dijkstra, &greedy] {
+        assert!(matches!(search.status(), SearchStatus::Found(path) if path.len() == 28));
+    }
+    // the heuristic saves work
+    assert!(greedy.expansions <= a_star.expansions && a_star.expansions < dijkstra.expansions);
+    assert_eq!(dijkstra.current(), Some(end));
+
+    // paused half way
+    let mut search = AStarSearch::new(&maze, start, end);
+    search.run(&maze, 5);
+    assert_eq!(search.status(), &SearchStatus::InProgress);
+    assert_eq!(search.closed_cells().count(), 5);
+    assert!(search
+        .open_cells()
+        .all(|cell| !search.closed_cells().any(|c| c == cell)));
+}
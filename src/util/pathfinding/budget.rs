@@ -0,0 +1,181 @@
+use super::{AStarSearch, SearchStatus};
+use crate::maze::{Coord, Maze};
+use std::time::{Duration, Instant};
+
+// expansions between checks of the clock
+const EXPANSIONS_PER_TIME_CHECK: usize = 16;
+
+/// How much path-finding may happen each frame, whichever runs out first.
+#[derive(Debug, Clone)]
+pub struct PathfindingBudget {
+    pub max_expansions: usize,
+    pub max_time: Duration,
+}
+
+impl Default for PathfindingBudget {
+    fn default() -> Self {
+        Self {
+            max_expansions: 2000,
+            max_time: Duration::from_micros(1000),
+        }
+    }
+}
+
+/// Searches waiting for their turn, each for an agent identified by `K`. Searches that don't finish within
+///  the budget of a frame are continued in the next one.
+pub struct PathRequests<K> {
+    pending: Vec<(K, AStarSearch)>,
+    /// nodes expanded during the last `run`
+    pub expansions_last_run: usize,
+}
+
+impl<K> Default for PathRequests<K> {
+    fn default() -> Self {
+        Self {
+            pending: Vec::new(),
+            expansions_last_run: 0,
+        }
+    }
+}
+
+impl<K: PartialEq> PathRequests<K> {
+    /// queues a search, replacing the one the agent was waiting for if any
+    pub fn request(&mut self, maze: &Maze, agent: K, start: Coord, end: Coord) {
+        self.cancel(&agent);
+        self.pending
+            .push((agent, AStarSearch::new(maze, start, end)));
+    }
+
+    pub fn cancel(&mut self, agent: &K) {
+        self.pending.retain(|(pending, _)| pending != agent);
+    }
+
+    pub fn retain(&mut self, keep: impl Fn(&K) -> bool) {
+        self.pending.retain(|(agent, _)| keep(agent));
+    }
+
+    /// starts every pending search over, for when the maze changed under them
+    pub fn restart(&mut self, maze: &Maze) {
+        for (_, search) in self.pending.iter_mut() {
+            *search = AStarSearch::with_algorithm(maze, search.start, search.end, search.algorithm);
+        }
+    }
+
+    pub fn is_pending(&self, agent: &K) -> bool {
+        self.pending.iter().any(|(pending, _)| pending == agent)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Works on the searches within the budget, the ones starting closest to `focus` (the player) first.
+    /// Returns the finished searches, None for the ones without a path.
+    pub fn run(
+        &mut self,
+        maze: &Maze,
+        budget: &PathfindingBudget,
+        focus: Coord,
+    ) -> Vec<(K, Option<Vec<Coord>>)> {
+        let distance = |(x, y): Coord| x.abs_diff(focus.0) + y.abs_diff(focus.1);
+        self.pending
+            .sort_by_key(|(_, search)| distance(search.start));
+
+        let started_at = Instant::now();
+        let mut expansions_left = budget.max_expansions;
+        self.expansions_last_run = 0;
+
+        let mut finished = Vec::new();
+        // searches are worked on until they're done, the next one moves into the same index then
+        while !self.pending.is_empty() && expansions_left > 0 {
+            if started_at.elapsed() >= budget.max_time {
+                break;
+            }
+
+            let search = &mut self.pending[0].1;
+            let before = search.expansions;
+            let status = search.run(maze, expansions_left.min(EXPANSIONS_PER_TIME_CHECK));
+            let expanded = search.expansions - before;
+            expansions_left -= expanded;
+            self.expansions_last_run += expanded;
+
+            let path = match status {
+                SearchStatus::InProgress => continue,
+                SearchStatus::Found(path) => Some(path),
+                SearchStatus::NotFound => None,
+            };
+            finished.push((self.pending.remove(0).0, path));
+        }
+
+        finished
+    }
+}
+
+#[test]
+fn test_searches_carry_over_and_closest_go_first() {
+    use super::a_star;
+
+    let maze = Maze::new_empty(30, 30);
+    let player = (0, 0);
+    let starts = [(29, 29), (5, 5), (15, 15)];
+
+    let mut requests = PathRequests::default();
+    for (agent, &start) in starts.iter().enumerate() {
+        requests.request(&maze, agent, start, player);
+    }
+
+    let budget = PathfindingBudget {
+        max_expansions: 25,
+        max_time: Duration::from_secs(10),
+    };
+
+    let mut finished_order = Vec::new();
+    let mut runs = 0;
+    while !requests.is_empty() {
+        for (agent, path) in requests.run(&maze, &budget, player) {
+            assert!(requests.expansions_last_run <= budget.max_expansions);
+            assert_eq!(
+                path.map(|path| path.len()),
+                a_star(&maze, starts[agent], player).map(|path| path.len())
+            );
+            finished_order.push(agent);
+        }
+        runs += 1;
+    }
+
+    assert_eq!(finished_order, vec![1, 2, 0]);
+    // too much work for a single frame
+    assert!(runs > 1);
+}
+
+#[test]
+fn test_restart_after_maze_change() {
+    use crate::maze::{Symbol, SymbolConsts};
+
+    let mut maze = Maze::new_empty(10, 3);
+    let mut requests = PathRequests::default();
+    requests.request(&maze, 0, (0, 1), (9, 1));
+
+    let budget = PathfindingBudget {
+        max_expansions: 3,
+        max_time: Duration::from_secs(10),
+    };
+    assert!(requests.run(&maze, &budget, (9, 1)).is_empty());
+
+    // a wall across the middle row, the search has to go around it
+    maze.set((5, 1), Symbol::BLOCKED);
+    requests.restart(&maze);
+
+    let unlimited = PathfindingBudget {
+        max_expansions: usize::MAX,
+        ..budget
+    };
+    let finished = requests.run(&maze, &unlimited, (9, 1));
+    let path = finished[0].1.as_ref().unwrap();
+    assert!(!path.contains(&(5, 1)));
+    assert_eq!(path.len(), 12);
+}
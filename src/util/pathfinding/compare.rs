@@ -0,0 +1,258 @@
+use super::{
+    path_length, theta_star_counting_expansions, AStarSearch, DStarLite, HpaStar, SearchAlgorithm,
+    SearchStatus,
+};
+use crate::maze::{Coord, Maze};
+use anyhow::*;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// cluster size used for the hierarchical backend
+const HPA_CLUSTER_SIZE: usize = 8;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Backend {
+    AStar,
+    Dijkstra,
+    GreedyBestFirst,
+    ThetaStar,
+    HpaStar,
+    DStarLite,
+}
+
+impl Backend {
+    pub const ALL: [Self; 6] = [
+        Self::AStar,
+        Self::Dijkstra,
+        Self::GreedyBestFirst,
+        Self::ThetaStar,
+        Self::HpaStar,
+        Self::DStarLite,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::AStar => "A*",
+            Self::Dijkstra => "Dijkstra",
+            Self::GreedyBestFirst => "Greedy best-first",
+            Self::ThetaStar => "Theta*",
+            Self::HpaStar => "HPA*",
+            Self::DStarLite => "D* Lite",
+        }
+    }
+
+    /// the path and the number of nodes expanded to find it
+    fn find_path(
+        self,
+        maze: &Maze,
+        hpa: &HpaStar,
+        start: Coord,
+        end: Coord,
+    ) -> (Option<Vec<Coord>>, usize) {
+        let search = |algorithm| {
+            let mut search = AStarSearch::with_algorithm(maze, start, end, algorithm);
+            let path = match search.run(maze, usize::MAX) {
+                SearchStatus::Found(path) => Some(path),
+                _ => None,
+            };
+            (path, search.expansions)
+        };
+
+        match self {
+            Self::AStar => search(SearchAlgorithm::AStar),
+            Self::Dijkstra => search(SearchAlgorithm::Dijkstra),
+            Self::GreedyBestFirst => search(SearchAlgorithm::GreedyBestFirst),
+            Self::ThetaStar => {
+                let mut expansions = 0;
+                let path = theta_star_counting_expansions(maze, start, end, &mut expansions);
+                (path, expansions)
+            }
+            Self::HpaStar => {
+                let before = hpa.expansions();
+                let path = hpa.find_path(maze, start, end);
+                (path, hpa.expansions() - before)
+            }
+            Self::DStarLite => {
+                let planner = DStarLite::new(maze, start, end);
+                (planner.path(maze), planner.expansions)
+            }
+        }
+    }
+}
+
+/// One path query run by one backend.
+#[derive(Debug, Clone)]
+pub struct ComparisonRow {
+    pub maze: String,
+    pub backend: Backend,
+    pub start: Coord,
+    pub end: Coord,
+    pub nodes_expanded: usize,
+    /// waypoints in the path, None if no path was found
+    pub path_length: Option<usize>,
+    /// distance walked along the path
+    pub path_cost: Option<f32>,
+    pub time: Duration,
+}
+
+/// Runs every backend from the player spawn to each enemy spawn of the maze.
+/// The time spent building the HPA* abstract graph isn't part of its queries.
+pub fn compare_backends(maze_name: &str, maze: &Maze) -> Vec<ComparisonRow> {
+    let start = match maze.player_spawn_coord() {
+        Some(start) => start,
+        None => return Vec::new(),
+    };
+    let hpa = HpaStar::new(maze, HPA_CLUSTER_SIZE);
+
+    let mut rows = Vec::new();
+    for end in maze.enemy_spawn_coords() {
+        for backend in Backend::ALL {
+            let started_at = Instant::now();
+            let (path, nodes_expanded) = backend.find_path(maze, &hpa, start, end);
+            let time = started_at.elapsed();
+
+            rows.push(ComparisonRow {
+                maze: maze_name.to_string(),
+                backend,
+                start,
+                end,
+                nodes_expanded,
+                path_length: path.as_ref().map(Vec::len),
+                path_cost: path.as_deref().map(path_length),
+                time,
+            });
+        }
+    }
+    rows
+}
+
+/// `compare_backends` over every maze file (`.txt`) in a directory, in order of file name. Files that aren't
+///  playable mazes are skipped.
+pub fn compare_backends_in_dir(dir: impl AsRef<Path>) -> Result<Vec<ComparisonRow>> {
+    let dir = dir.as_ref();
+    let mut paths = std::fs::read_dir(dir)
+        .with_context(|| format!("couldn't read maze directory {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.retain(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt"));
+    paths.sort();
+
+    let mut rows = Vec::new();
+    for path in paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let maze = match Maze::load_validated(&path.to_string_lossy()) {
+            Ok((maze, issues)) => match issues.iter().find(|issue| issue.is_error()) {
+                Some(error) => {
+                    eprintln!("skipping {}: {}", name, error);
+                    continue;
+                }
+                None => maze,
+            },
+            Err(err) => {
+                eprintln!("skipping {}: {:?}", name, err);
+                continue;
+            }
+        };
+
+        rows.extend(compare_backends(&name, &maze));
+    }
+    Ok(rows)
+}
+
+const COLUMNS: [&str; 8] = [
+    "maze",
+    "backend",
+    "start",
+    "end",
+    "nodes expanded",
+    "path length",
+    "path cost",
+    "time (µs)",
+];
+
+fn columns(row: &ComparisonRow) -> [String; 8] {
+    let coord = |(x, y): Coord| format!("({} {})", x, y);
+    [
+        row.maze.clone(),
+        row.backend.name().to_string(),
+        coord(row.start),
+        coord(row.end),
+        row.nodes_expanded.to_string(),
+        row.path_length
+            .map_or_else(|| "-".to_string(), |length| length.to_string()),
+        row.path_cost
+            .map_or_else(|| "-".to_string(), |cost| format!("{:.2}", cost)),
+        row.time.as_micros().to_string(),
+    ]
+}
+
+pub fn to_csv(rows: &[ComparisonRow]) -> String {
+    let mut csv = COLUMNS.join(",");
+    csv.push('\n');
+    for row in rows {
+        csv.push_str(&columns(row).join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+pub fn to_markdown(rows: &[ComparisonRow]) -> String {
+    let line = |cells: &[String]| format!("| {} |\n", cells.join(" | "));
+
+    let header: Vec<String> = COLUMNS.iter().map(|column| column.to_string()).collect();
+    let mut markdown = line(&header);
+    markdown.push_str(&line(&vec!["---".to_string(); COLUMNS.len()]));
+    for row in rows {
+        markdown.push_str(&line(&columns(row)));
+    }
+    markdown
+}
+
+#[test]
+fn test_compare_backends_in_dir() {
+    use crate::util::Array2D;
+
+    let dir = std::env::temp_dir().join("ai_compare_backends_test");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let maze = Maze {
+        grid: Array2D::from(
+            "\
+            ##########\n\
+            #P.......#\n\
+            #.######.#\n\
+            #......#E#\n\
+            ####E###.#\n\
+            ##########\n"
+                .to_string(),
+        ),
+    };
+    maze.save_to_file(dir.join("a.txt").to_str().unwrap());
+    // not mazes, or not playable ones
+    std::fs::write(dir.join("a.dot"), "graph {}\n").unwrap();
+    std::fs::write(dir.join("b.txt"), "#?#\n#P#\n").unwrap();
+
+    let rows = compare_backends_in_dir(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // two enemies
+    assert_eq!(rows.len(), 2 * Backend::ALL.len());
+    for row in &rows {
+        assert_eq!(row.maze, "a.txt");
+        assert!(row.nodes_expanded > 0, "{:?}", row);
+        assert!(row.path_length.is_some(), "{:?}", row);
+    }
+
+    let shortest = rows
+        .iter()
+        .filter(|row| row.end == (8, 3))
+        .map(|row| row.path_cost.unwrap())
+        .fold(f32::MAX, f32::min);
+    assert_eq!(shortest, 9.);
+
+    let csv = to_csv(&rows);
+    assert_eq!(csv.lines().count(), rows.len() + 1);
+    assert!(csv.starts_with("maze,backend,"));
+    let markdown = to_markdown(&rows);
+    assert_eq!(markdown.lines().count(), rows.len() + 2);
+}
@@ -0,0 +1,257 @@
+use super::{reconstruct_path, walkable_neighbours};
+use crate::maze::{Coord, Maze, Symbol, SymbolConsts};
+use crate::util::Array2D;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Cells (and moves between cells) that already planned agents occupy at each timestep.
+#[derive(Debug, Clone)]
+pub struct ReservationTable {
+    /// how many timesteps ahead paths are planned and reserved
+    pub horizon: usize,
+    /// if agents stay at the end of their path until the horizon, see `without_holding_goals`
+    pub hold_goals: bool,
+    cells: HashSet<(Coord, usize)>,
+    // (from, to, arrival time) moves that would swap places with an agent going the other way
+    moves: HashSet<(Coord, Coord, usize)>,
+}
+
+impl ReservationTable {
+    pub fn new(horizon: usize) -> Self {
+        Self {
+            horizon,
+            hold_goals: true,
+            cells: HashSet::new(),
+            moves: HashSet::new(),
+        }
+    }
+
+    /// for agents that are gone once they reach their goal (an enemy that touches the player dies)
+    pub fn without_holding_goals(mut self) -> Self {
+        self.hold_goals = false;
+        self
+    }
+
+    pub fn is_cell_free(&self, cell: Coord, time: usize) -> bool {
+        !self.cells.contains(&(cell, time))
+    }
+
+    pub fn is_move_free(&self, from: Coord, to: Coord, arrival_time: usize) -> bool {
+        self.is_cell_free(to, arrival_time) && !self.moves.contains(&(from, to, arrival_time))
+    }
+
+    /// reserves a single cell at a single timestep, for agents without a path
+    pub fn reserve_cell(&mut self, cell: Coord, time: usize) {
+        self.cells.insert((cell, time));
+    }
+
+    /// Reserves a path indexed by timestep.
+    pub fn reserve_path(&mut self, path: &[Coord]) {
+        for (time, &cell) in path.iter().enumerate() {
+            self.cells.insert((cell, time));
+            if time > 0 {
+                // nobody may go the opposite way at the same time
+                self.moves.insert((cell, path[time - 1], time));
+            }
+        }
+
+        if let (true, Some(&last)) = (self.hold_goals, path.last()) {
+            for time in path.len()..=self.horizon {
+                self.cells.insert((last, time));
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct SpaceTimeNode {
+    coord: Coord,
+    time: usize,
+    g_cost: u32,
+    h_cost: u32,
+}
+
+impl SpaceTimeNode {
+    fn f_cost(&self) -> u32 {
+        self.g_cost + self.h_cost
+    }
+}
+
+impl Eq for SpaceTimeNode {}
+
+impl PartialEq for SpaceTimeNode {
+    // the same keys as the ordering
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Ord for SpaceTimeNode {
+    // reversed to make the BinaryHeap a min-heap
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .f_cost()
+            .cmp(&self.f_cost())
+            .then_with(|| other.h_cost.cmp(&self.h_cost))
+    }
+}
+
+impl PartialOrd for SpaceTimeNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Exact distance to the goal from every cell, ignoring other agents. Used as the heuristic as it stays
+///  admissible no matter how many detours the reservations cause.
+pub(crate) fn distances_to(maze: &Maze, goal: Coord) -> Array2D<Option<u32>> {
+    maze.bfs_distances([goal], |&symbol| symbol != Symbol::BLOCKED)
+}
+
+/// Cooperative A*: searches through space and time around the paths already in the reservation table.
+/// The returned path has one cell per timestep, so standing still shows up as the same cell repeated.
+///
+/// If the goal can't be reached (and held, see `ReservationTable::hold_goals`) within the horizon, the path
+///  runs until the horizon and ends as close to the goal as the search got.
+/// The path isn't reserved, use `ReservationTable::reserve_path` for that.
+pub fn cooperative_a_star(
+    maze: &Maze,
+    reservations: &ReservationTable,
+    start: Coord,
+    goal: Coord,
+) -> Option<Vec<Coord>> {
+    cooperative_a_star_with_cost(maze, reservations, start, goal, |_| 1)
+}
+
+/// `cooperative_a_star` where moving into a cell costs `cost(cell)` instead of 1. Waiting always costs 1.
+pub fn cooperative_a_star_with_cost(
+    maze: &Maze,
+    reservations: &ReservationTable,
+    start: Coord,
+    goal: Coord,
+    cost: impl Fn(Coord) -> u32,
+) -> Option<Vec<Coord>> {
+    if !maze.is_walkable(start) || !maze.is_walkable(goal) {
+        return None;
+    }
+    let distances = distances_to(maze, goal);
+    let h_cost = |cell: Coord| *distances.get(cell);
+
+    let horizon = reservations.horizon;
+    let can_stay_at_goal = |time: usize| {
+        !reservations.hold_goals || (time..=horizon).all(|t| reservations.is_cell_free(goal, t))
+    };
+
+    let mut open_set = BinaryHeap::new();
+    let mut closed_set = HashSet::new();
+    let mut parents: HashMap<(Coord, usize), (Coord, usize)> = HashMap::new();
+    let mut g_costs: HashMap<(Coord, usize), u32> = HashMap::new();
+
+    open_set.push(SpaceTimeNode {
+        coord: start,
+        time: 0,
+        g_cost: 0,
+        h_cost: h_cost(start)?,
+    });
+
+    while let Some(current) = open_set.pop() {
+        let key = (current.coord, current.time);
+
+        let reached_goal = current.coord == goal && can_stay_at_goal(current.time);
+        if reached_goal || current.time == horizon {
+            let path = reconstruct_path(&parents, key);
+            return Some(path.into_iter().map(|(coord, _)| coord).collect());
+        }
+
+        if !closed_set.insert(key) {
+            continue;
+        }
+
+        let time = current.time + 1;
+        // waiting is moving to the cell you're already in
+        let moves = walkable_neighbours(maze, current.coord).chain(std::iter::once(current.coord));
+
+        for next in moves {
+            if closed_set.contains(&(next, time))
+                || !reservations.is_move_free(current.coord, next, time)
+            {
+                continue;
+            }
+
+            let step_cost = if next == current.coord { 1 } else { cost(next) };
+            let g_cost = current.g_cost + step_cost;
+
+            if g_costs.get(&(next, time)).is_none_or(|&old| g_cost < old) {
+                g_costs.insert((next, time), g_cost);
+                parents.insert((next, time), key);
+                open_set.push(SpaceTimeNode {
+                    coord: next,
+                    time,
+                    g_cost,
+                    // everything reachable from the start can reach the goal
+                    h_cost: h_cost(next).unwrap(),
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// Plans conflict free paths for a group of agents, in order of priority. Each entry is a (start, goal) pair.
+pub fn plan_cooperative_paths(
+    maze: &Maze,
+    agents: &[(Coord, Coord)],
+    horizon: usize,
+) -> Vec<Option<Vec<Coord>>> {
+    let mut reservations = ReservationTable::new(horizon);
+
+    agents
+        .iter()
+        .map(|&(start, goal)| {
+            let path = cooperative_a_star(maze, &reservations, start, goal);
+            if let Some(path) = &path {
+                reservations.reserve_path(path);
+            }
+            path
+        })
+        .collect()
+}
+
+#[test]
+fn test_cooperative_paths_are_conflict_free() {
+    // two agents swapping ends of a corridor, one of them has to step into the alcove
+    let maze = Maze {
+        grid: Array2D::from(
+            "\
+            #######\n\
+            #.....#\n\
+            ####.##\n\
+            #######\n"
+                .to_string(),
+        ),
+    };
+    let horizon = 16;
+    let agents = [((1, 1), (5, 1)), ((5, 1), (1, 1))];
+
+    let paths: Vec<Vec<Coord>> = plan_cooperative_paths(&maze, &agents, horizon)
+        .into_iter()
+        .map(Option::unwrap)
+        .collect();
+
+    for (path, &(start, goal)) in paths.iter().zip(agents.iter()) {
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&goal));
+    }
+
+    let at = |path: &Vec<Coord>, time: usize| path[time.min(path.len() - 1)];
+    for time in 0..=horizon {
+        let (a, b) = (at(&paths[0], time), at(&paths[1], time));
+        assert_ne!(a, b, "both agents in {:?} at {}", a, time);
+
+        if time > 0 {
+            let swapped = a == at(&paths[1], time - 1) && b == at(&paths[0], time - 1);
+            assert!(!swapped, "agents swapped places at {}", time);
+        }
+    }
+}
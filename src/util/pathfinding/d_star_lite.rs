@@ -0,0 +1,230 @@
+use super::walkable_neighbours;
+use crate::maze::{Coord, Maze};
+use crate::util::Array2D;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+const INFINITY: u32 = u32::MAX;
+
+// (f cost, g cost), compared lexicographically
+type Key = (u32, u32);
+
+fn h_cost(a: Coord, b: Coord) -> u32 {
+    (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as u32
+}
+
+/// D* Lite: searches backwards from the goal and keeps its results around, so when cells of the maze change
+///  only the part of the search affected by the change is redone. The start may move along the path without
+///  a new search. A new goal needs a new planner.
+#[derive(Debug)]
+pub struct DStarLite {
+    start: Coord,
+    goal: Coord,
+    /// cells expanded over all searches so far
+    pub expansions: usize,
+    // added to the keys every time the start moves, instead of reordering the whole open set
+    key_modifier: u32,
+    // cost of the cheapest path from each cell to the goal found so far
+    g: Array2D<u32>,
+    // one step lookahead of g, a cell is consistent when both are the same
+    rhs: Array2D<u32>,
+    open_set: BinaryHeap<Reverse<(Key, Coord)>>,
+    // the current key of every cell in the open set, entries in the heap with other keys are outdated
+    open_keys: HashMap<Coord, Key>,
+}
+
+impl DStarLite {
+    pub fn new(maze: &Maze, start: Coord, goal: Coord) -> Self {
+        let mut planner = Self {
+            start,
+            goal,
+            expansions: 0,
+            key_modifier: 0,
+            g: Array2D::new(maze.width, maze.height, INFINITY),
+            rhs: Array2D::new(maze.width, maze.height, INFINITY),
+            open_set: BinaryHeap::new(),
+            open_keys: HashMap::new(),
+        };
+
+        if maze.is_walkable(goal) {
+            planner.rhs.set(goal, 0);
+            planner.insert(goal);
+        }
+        planner.compute_shortest_path(maze);
+        planner
+    }
+
+    pub fn start(&self) -> Coord {
+        self.start
+    }
+
+    pub fn goal(&self) -> Coord {
+        self.goal
+    }
+
+    /// The agent moved, the search stays valid.
+    pub fn move_start(&mut self, maze: &Maze, start: Coord) {
+        self.key_modifier += h_cost(self.start, start);
+        self.start = start;
+        self.compute_shortest_path(maze);
+    }
+
+    /// Repairs the search after the walkability of the cells changed. The maze must already be updated.
+    pub fn update_cells(&mut self, maze: &Maze, changed: &[Coord]) {
+        for &cell in changed {
+            self.update_cell(maze, cell);
+            // the cost of every edge into and out of the cell changed
+            for neighbour in maze.neighbours_4(cell) {
+                self.update_cell(maze, neighbour);
+            }
+        }
+        self.compute_shortest_path(maze);
+    }
+
+    /// Shortest path from the start to the goal (both included), None if there is none.
+    pub fn path(&self, maze: &Maze) -> Option<Vec<Coord>> {
+        if *self.g.get(self.start) == INFINITY || !maze.is_walkable(self.start) {
+            return None;
+        }
+
+        let mut path = vec![self.start];
+        let mut current = self.start;
+        while current != self.goal {
+            // downhill along g, every step gets one closer to the goal
+            current = walkable_neighbours(maze, current)
+                .min_by_key(|&neighbour| *self.g.get(neighbour))
+                .filter(|&next| *self.g.get(next) < *self.g.get(current))?;
+            path.push(current);
+        }
+        Some(path)
+    }
+
+    fn key(&self, cell: Coord) -> Key {
+        let g = (*self.g.get(cell)).min(*self.rhs.get(cell));
+        (
+            g.saturating_add(h_cost(self.start, cell))
+                .saturating_add(self.key_modifier),
+            g,
+        )
+    }
+
+    fn insert(&mut self, cell: Coord) {
+        let key = self.key(cell);
+        self.open_keys.insert(cell, key);
+        self.open_set.push(Reverse((key, cell)));
+    }
+
+    /// the lowest key in the open set, dropping outdated heap entries on the way
+    fn top(&mut self) -> Option<(Key, Coord)> {
+        while let Some(&Reverse((key, cell))) = self.open_set.peek() {
+            if self.open_keys.get(&cell) == Some(&key) {
+                return Some((key, cell));
+            }
+            self.open_set.pop();
+        }
+        None
+    }
+
+    fn update_cell(&mut self, maze: &Maze, cell: Coord) {
+        if cell != self.goal {
+            let rhs = if maze.is_walkable(cell) {
+                walkable_neighbours(maze, cell)
+                    .map(|neighbour| self.g.get(neighbour).saturating_add(1))
+                    .min()
+                    .unwrap_or(INFINITY)
+            } else {
+                INFINITY
+            };
+            self.rhs.set(cell, rhs);
+        }
+
+        self.open_keys.remove(&cell);
+        if self.g.get(cell) != self.rhs.get(cell) {
+            self.insert(cell);
+        }
+    }
+
+    fn compute_shortest_path(&mut self, maze: &Maze) {
+        while let Some((old_key, cell)) = self.top() {
+            let start_consistent = self.g.get(self.start) == self.rhs.get(self.start);
+            if old_key >= self.key(self.start) && start_consistent {
+                break;
+            }
+
+            let new_key = self.key(cell);
+            if old_key < new_key {
+                // moved since it was queued, requeue with the current key
+                self.insert(cell);
+                continue;
+            }
+
+            self.open_keys.remove(&cell);
+            self.expansions += 1;
+            let (g, rhs) = (*self.g.get(cell), *self.rhs.get(cell));
+            if g > rhs {
+                self.g.set(cell, rhs);
+            } else {
+                self.g.set(cell, INFINITY);
+                self.update_cell(maze, cell);
+            }
+
+            let neighbours: Vec<Coord> = walkable_neighbours(maze, cell).collect();
+            for neighbour in neighbours {
+                self.update_cell(maze, neighbour);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_d_star_lite_matches_a_star_after_random_edits() {
+    use super::a_star;
+    use crate::maze::{Symbol, SymbolConsts};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(31);
+    let (width, height) = (12, 10);
+    let mut maze = Maze::new_empty(width, height);
+
+    let (start, goal) = ((0, 0), (width - 1, height - 1));
+    let mut planner = DStarLite::new(&maze, start, goal);
+    let mut position = start;
+
+    for _ in 0..200 {
+        // toggle a few cells, never the ends
+        let changed: Vec<Coord> = (0..3)
+            .map(|_| (rng.gen_range(0..width), rng.gen_range(0..height)))
+            .filter(|&cell| cell != position && cell != goal)
+            .collect();
+        for &cell in &changed {
+            let symbol = if maze.is_walkable(cell) {
+                Symbol::BLOCKED
+            } else {
+                Symbol::FREE
+            };
+            maze.set(cell, symbol);
+        }
+        planner.update_cells(&maze, &changed);
+
+        let path = planner.path(&maze);
+        let expected = a_star(&maze, position, goal);
+        assert_eq!(
+            path.as_ref().map(Vec::len),
+            expected.as_ref().map(Vec::len),
+            "\n{}",
+            maze.to_string()
+        );
+
+        if let Some(path) = path {
+            assert!(path.windows(2).all(|w| h_cost(w[0], w[1]) == 1));
+            assert!(path.iter().all(|&cell| maze.is_walkable(cell)));
+
+            // walk a step along the path now and then
+            if path.len() > 1 && rng.gen_bool(0.5) {
+                position = path[1];
+                planner.move_start(&maze, position);
+            }
+        }
+    }
+}
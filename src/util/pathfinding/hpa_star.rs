@@ -0,0 +1,391 @@
+use super::{reconstruct_path, walkable_neighbours};
+use crate::maze::{Coord, Maze};
+use crate::util::Direction;
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+/// index of a cluster, (x, y) like cells
+pub type ClusterId = (usize, usize);
+
+// entrances longer than this get a transition at both ends instead of one in the middle
+const MAX_SINGLE_TRANSITION_LENGTH: usize = 6;
+
+/// Hierarchical path-finding A*. The grid is split into square clusters, connected to each other through
+///  entrances on the borders between them. Path queries search the much smaller abstract graph of entrances
+///  first, then refine each step of it with a search inside a single cluster.
+#[derive(Debug)]
+pub struct HpaStar {
+    pub cluster_size: usize,
+    width: usize,
+    height: usize,
+    // pairs of cells (one on each side) where agents may cross the border between two clusters,
+    //  keyed by the two clusters with the left/lower one first
+    transitions: HashMap<(ClusterId, ClusterId), Vec<(Coord, Coord)>>,
+    // shortest distances between the transition cells of a cluster, without leaving the cluster
+    intra_edges: HashMap<ClusterId, HashMap<Coord, Vec<(Coord, u32)>>>,
+    // nodes expanded by all searches so far, abstract and local. Counted from &self queries too
+    expansions: Cell<usize>,
+}
+
+impl HpaStar {
+    pub fn new(maze: &Maze, cluster_size: usize) -> Self {
+        assert!(cluster_size > 0, "clusters must have at least one cell");
+
+        let mut hpa = Self {
+            cluster_size,
+            width: maze.width,
+            height: maze.height,
+            transitions: HashMap::new(),
+            intra_edges: HashMap::new(),
+            expansions: Cell::new(0),
+        };
+
+        let clusters: Vec<ClusterId> = hpa.clusters().collect();
+        for &cluster in &clusters {
+            hpa.build_transitions(maze, cluster);
+        }
+        for &cluster in &clusters {
+            hpa.build_intra_edges(maze, cluster);
+        }
+        hpa
+    }
+
+    /// nodes expanded so far, building the abstract graph included
+    pub fn expansions(&self) -> usize {
+        self.expansions.get()
+    }
+
+    pub fn cluster_of(&self, (x, y): Coord) -> ClusterId {
+        (x / self.cluster_size, y / self.cluster_size)
+    }
+
+    /// Rebuilds the part of the abstract graph around a cell that changed. The maze must already be updated.
+    pub fn update_cell(&mut self, maze: &Maze, coord: Coord) {
+        let cluster = self.cluster_of(coord);
+        self.build_transitions(maze, cluster);
+
+        // the transitions of the neighbouring clusters may have changed too
+        self.build_intra_edges(maze, cluster);
+        for neighbour in self.neighbouring_clusters(cluster) {
+            self.build_intra_edges(maze, neighbour);
+        }
+    }
+
+    /// Path from start to end (both included), one cell per step. Close to, but not always, the shortest.
+    pub fn find_path(&self, maze: &Maze, start: Coord, end: Coord) -> Option<Vec<Coord>> {
+        if !maze.is_walkable(start) || !maze.is_walkable(end) {
+            return None;
+        }
+
+        let (start_cluster, end_cluster) = (self.cluster_of(start), self.cluster_of(end));
+        if start_cluster == end_cluster {
+            if let Some(path) = self.local_path(maze, start, end) {
+                return Some(path);
+            }
+        }
+
+        let abstract_path = self.abstract_path(maze, start, end)?;
+
+        // refine every abstract step, they're either within one cluster or a single step across a border
+        let mut path = vec![start];
+        for step in abstract_path.windows(2) {
+            let (from, to) = (step[0], step[1]);
+            let segment = if self.cluster_of(from) == self.cluster_of(to) {
+                self.local_path(maze, from, to)?
+            } else {
+                vec![from, to]
+            };
+            path.extend_from_slice(&segment[1..]);
+        }
+        Some(path)
+    }
+
+    fn clusters(&self) -> impl Iterator<Item = ClusterId> {
+        let clusters_x = self.width.div_ceil(self.cluster_size);
+        let clusters_y = self.height.div_ceil(self.cluster_size);
+        (0..clusters_y).flat_map(move |y| (0..clusters_x).map(move |x| (x, y)))
+    }
+
+    fn neighbouring_clusters(&self, cluster: ClusterId) -> Vec<ClusterId> {
+        let last = self.cluster_of((self.width - 1, self.height - 1));
+        Direction::ALL
+            .into_iter()
+            .filter_map(|dir| dir.step(cluster, (last.0 + 1, last.1 + 1)))
+            .collect()
+    }
+
+    /// the cells of the cluster, (min x, min y) and (max x, max y) both included
+    fn bounds(&self, (x, y): ClusterId) -> (Coord, Coord) {
+        let min = (x * self.cluster_size, y * self.cluster_size);
+        let max = (
+            (min.0 + self.cluster_size).min(self.width) - 1,
+            (min.1 + self.cluster_size).min(self.height) - 1,
+        );
+        (min, max)
+    }
+
+    fn contains(&self, cluster: ClusterId, (x, y): Coord) -> bool {
+        let (min, max) = self.bounds(cluster);
+        (min.0..=max.0).contains(&x) && (min.1..=max.1).contains(&y)
+    }
+
+    /// finds the entrances on the borders of a cluster to its right and upper neighbours, and has its left
+    ///  and lower neighbours find theirs with it
+    fn build_transitions(&mut self, maze: &Maze, cluster: ClusterId) {
+        let mut borders = vec![cluster];
+        if cluster.0 > 0 {
+            borders.push((cluster.0 - 1, cluster.1));
+        }
+        if cluster.1 > 0 {
+            borders.push((cluster.0, cluster.1 - 1));
+        }
+
+        for first in borders {
+            let (min, max) = self.bounds(first);
+
+            if max.0 + 1 < self.width {
+                let pairs = (min.1..=max.1)
+                    .map(|y| ((max.0, y), (max.0 + 1, y)))
+                    .collect();
+                let right = (first.0 + 1, first.1);
+                self.transitions
+                    .insert((first, right), entrance_transitions(maze, pairs));
+            }
+            if max.1 + 1 < self.height {
+                let pairs = (min.0..=max.0)
+                    .map(|x| ((x, max.1), (x, max.1 + 1)))
+                    .collect();
+                let up = (first.0, first.1 + 1);
+                self.transitions
+                    .insert((first, up), entrance_transitions(maze, pairs));
+            }
+        }
+    }
+
+    /// the cells of the cluster that are on one end of a transition
+    fn transition_cells(&self, cluster: ClusterId) -> HashSet<Coord> {
+        self.neighbouring_clusters(cluster)
+            .into_iter()
+            .filter_map(|neighbour| {
+                let key = if cluster < neighbour {
+                    (cluster, neighbour)
+                } else {
+                    (neighbour, cluster)
+                };
+                self.transitions.get(&key)
+            })
+            .flatten()
+            .flat_map(|&(a, b)| [a, b])
+            .filter(|&cell| self.contains(cluster, cell))
+            .collect()
+    }
+
+    fn build_intra_edges(&mut self, maze: &Maze, cluster: ClusterId) {
+        let cells = self.transition_cells(cluster);
+
+        let edges = cells
+            .iter()
+            .map(|&from| {
+                let distances = self.local_search(maze, from);
+                let reachable = cells
+                    .iter()
+                    .filter(|&&to| to != from)
+                    .filter_map(|&to| distances.get(&to).map(|&(distance, _)| (to, distance)))
+                    .collect();
+                (from, reachable)
+            })
+            .collect();
+
+        self.intra_edges.insert(cluster, edges);
+    }
+
+    /// breadth first search from a cell that doesn't leave its cluster, (distance, parent) of every cell reached
+    fn local_search(&self, maze: &Maze, from: Coord) -> HashMap<Coord, (u32, Option<Coord>)> {
+        let cluster = self.cluster_of(from);
+        let mut reached = HashMap::from([(from, (0, None))]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(cell) = queue.pop_front() {
+            self.expansions.set(self.expansions.get() + 1);
+            let distance = reached[&cell].0;
+            for neighbour in walkable_neighbours(maze, cell) {
+                if self.contains(cluster, neighbour) && !reached.contains_key(&neighbour) {
+                    reached.insert(neighbour, (distance + 1, Some(cell)));
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        reached
+    }
+
+    fn local_path(&self, maze: &Maze, from: Coord, to: Coord) -> Option<Vec<Coord>> {
+        let reached = self.local_search(maze, from);
+        reached.get(&to)?;
+
+        let parents: HashMap<Coord, Coord> = reached
+            .into_iter()
+            .filter_map(|(cell, (_, parent))| parent.map(|parent| (cell, parent)))
+            .collect();
+        Some(reconstruct_path(&parents, to))
+    }
+
+    /// A* over the transitions, with the start and end connected to the transitions of their clusters
+    fn abstract_path(&self, maze: &Maze, start: Coord, end: Coord) -> Option<Vec<Coord>> {
+        let edges_to_cluster = |from: Coord| -> Vec<(Coord, u32)> {
+            let distances = self.local_search(maze, from);
+            self.transition_cells(self.cluster_of(from))
+                .into_iter()
+                .filter_map(|cell| distances.get(&cell).map(|&(distance, _)| (cell, distance)))
+                .collect()
+        };
+        let start_edges = edges_to_cluster(start);
+        // edges into the end, the graph is undirected
+        let end_edges: HashMap<Coord, u32> = edges_to_cluster(end).into_iter().collect();
+
+        let h_cost = |(x, y): Coord| (x.abs_diff(end.0) + y.abs_diff(end.1)) as u32;
+
+        let mut open_set = BinaryHeap::from([Reverse((h_cost(start), 0, start))]);
+        let mut g_costs = HashMap::from([(start, 0)]);
+        let mut parents = HashMap::new();
+        let mut closed_set = HashSet::new();
+
+        while let Some(Reverse((_, g_cost, current))) = open_set.pop() {
+            if current == end {
+                return Some(reconstruct_path(&parents, end));
+            }
+            if !closed_set.insert(current) {
+                continue;
+            }
+            self.expansions.set(self.expansions.get() + 1);
+
+            let mut edges = self.abstract_edges(current);
+            if current == start {
+                edges.extend_from_slice(&start_edges);
+            }
+            if let Some(&distance) = end_edges.get(&current) {
+                edges.push((end, distance));
+            }
+
+            for (next, cost) in edges {
+                let next_g_cost = g_cost + cost;
+                if g_costs.get(&next).is_none_or(|&old| next_g_cost < old) {
+                    g_costs.insert(next, next_g_cost);
+                    parents.insert(next, current);
+                    open_set.push(Reverse((next_g_cost + h_cost(next), next_g_cost, next)));
+                }
+            }
+        }
+        None
+    }
+
+    /// edges of a transition cell to the others in its cluster and across the border
+    fn abstract_edges(&self, cell: Coord) -> Vec<(Coord, u32)> {
+        let cluster = self.cluster_of(cell);
+        let mut edges = self
+            .intra_edges
+            .get(&cluster)
+            .and_then(|edges| edges.get(&cell))
+            .cloned()
+            .unwrap_or_default();
+
+        for neighbour in self.neighbouring_clusters(cluster) {
+            let key = if cluster < neighbour {
+                (cluster, neighbour)
+            } else {
+                (neighbour, cluster)
+            };
+            for &(a, b) in self.transitions.get(&key).into_iter().flatten() {
+                if a == cell {
+                    edges.push((b, 1));
+                } else if b == cell {
+                    edges.push((a, 1));
+                }
+            }
+        }
+        edges
+    }
+}
+
+/// Splits the pairs of cells facing each other along a border into entrances, runs where both cells are
+///  walkable, and picks the transitions of each entrance.
+fn entrance_transitions(maze: &Maze, pairs: Vec<(Coord, Coord)>) -> Vec<(Coord, Coord)> {
+    let mut transitions = Vec::new();
+    let mut add_entrance = |entrance: &[(Coord, Coord)]| {
+        if entrance.is_empty() {
+            return;
+        }
+        if entrance.len() > MAX_SINGLE_TRANSITION_LENGTH {
+            transitions.push(entrance[0]);
+            transitions.push(entrance[entrance.len() - 1]);
+        } else {
+            transitions.push(entrance[entrance.len() / 2]);
+        }
+    };
+
+    let mut entrance = Vec::new();
+    for (a, b) in pairs {
+        if maze.is_walkable(a) && maze.is_walkable(b) {
+            entrance.push((a, b));
+        } else {
+            add_entrance(&entrance);
+            entrance.clear();
+        }
+    }
+    add_entrance(&entrance);
+
+    transitions
+}
+
+#[test]
+fn test_hpa_star_matches_a_star() {
+    use super::a_star;
+    use crate::maze::{Symbol, SymbolConsts};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(33);
+    let (width, height) = (60, 45);
+    let mut maze = Maze::new_empty(width, height);
+    for (x, y) in (0..height).flat_map(|y| (0..width).map(move |x| (x, y))) {
+        if rng.gen_bool(0.25) {
+            maze.set((x, y), Symbol::BLOCKED);
+        }
+    }
+
+    let mut hpa = HpaStar::new(&maze, 8);
+    let random_cell = |rng: &mut StdRng| (rng.gen_range(0..width), rng.gen_range(0..height));
+
+    for _ in 0..100 {
+        // change a cell now and then
+        let cell = random_cell(&mut rng);
+        let symbol = if maze.is_walkable(cell) {
+            Symbol::BLOCKED
+        } else {
+            Symbol::FREE
+        };
+        maze.set(cell, symbol);
+        hpa.update_cell(&maze, cell);
+
+        let (start, end) = (random_cell(&mut rng), random_cell(&mut rng));
+        let path = hpa.find_path(&maze, start, end);
+        let shortest = a_star(&maze, start, end);
+        assert_eq!(
+            path.is_some(),
+            shortest.is_some(),
+            "{:?} to {:?}",
+            start,
+            end
+        );
+
+        if let (Some(path), Some(shortest)) = (path, shortest) {
+            assert_eq!((path[0], path[path.len() - 1]), (start, end));
+            assert!(path.iter().all(|&cell| maze.is_walkable(cell)));
+            assert!(path
+                .windows(2)
+                .all(|w| w[0].0.abs_diff(w[1].0) + w[0].1.abs_diff(w[1].1) == 1));
+            // a little longer than the shortest at most
+            assert!(path.len() as f32 <= shortest.len() as f32 * 1.5 + 4.);
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use crate::maze::{Coord, Maze};
+
+mod a_star;
+pub use a_star::*;
+
+mod budget;
+pub use budget::*;
+
+mod compare;
+pub use compare::*;
+
+mod cooperative;
+pub use cooperative::*;
+
+mod d_star_lite;
+pub use d_star_lite::*;
+
+mod hpa_star;
+pub use hpa_star::*;
+
+mod theta_star;
+pub use theta_star::*;
+
+/// the walkable cells next to the coordinate
+pub fn walkable_neighbours(maze: &Maze, coord: Coord) -> impl Iterator<Item = Coord> + '_ {
+    maze.neighbours_4(coord).filter(|&next| maze.is_walkable(next))
+}
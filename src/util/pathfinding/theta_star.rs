@@ -0,0 +1,199 @@
+use super::{reconstruct_path, walkable_neighbours};
+use crate::maze::{Coord, Maze};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+fn distance(a: Coord, b: Coord) -> f32 {
+    let (dx, dy) = (a.0.abs_diff(b.0) as f32, a.1.abs_diff(b.1) as f32);
+    (dx * dx + dy * dy).sqrt()
+}
+
+struct Node {
+    coord: Coord,
+    g_cost: f32,
+    h_cost: f32,
+}
+
+impl Node {
+    fn f_cost(&self) -> f32 {
+        self.g_cost + self.h_cost
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Ord for Node {
+    // reversed to make the BinaryHeap a min-heap
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .f_cost()
+            .total_cmp(&self.f_cost())
+            .then_with(|| other.h_cost.total_cmp(&self.h_cost))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Theta*: A* where a cell's parent may be any cell in line of sight instead of just a neighbour, so the path
+///  can go in any angle. Returns the corners of the path only, from start to end, with straight lines
+///  between them that don't touch a blocked cell.
+pub fn theta_star(maze: &Maze, start: Coord, end: Coord) -> Option<Vec<Coord>> {
+    theta_star_counting_expansions(maze, start, end, &mut 0)
+}
+
+/// `theta_star` that adds the number of cells it expands to `expansions`
+pub fn theta_star_counting_expansions(
+    maze: &Maze,
+    start: Coord,
+    end: Coord,
+    expansions: &mut usize,
+) -> Option<Vec<Coord>> {
+    if !maze.is_walkable(start) || !maze.is_walkable(end) {
+        return None;
+    }
+
+    let mut open_set = BinaryHeap::new();
+    let mut closed_set = HashSet::new();
+    let mut parents: HashMap<Coord, Coord> = HashMap::new();
+    let mut g_costs: HashMap<Coord, f32> = HashMap::new();
+
+    open_set.push(Node {
+        coord: start,
+        g_cost: 0.,
+        h_cost: distance(start, end),
+    });
+    g_costs.insert(start, 0.);
+
+    while let Some(current) = open_set.pop() {
+        if current.coord == end {
+            return Some(reconstruct_path(&parents, end));
+        }
+
+        if !closed_set.insert(current.coord) {
+            continue;
+        }
+        *expansions += 1;
+
+        for neighbour in walkable_neighbours(maze, current.coord) {
+            if closed_set.contains(&neighbour) {
+                continue;
+            }
+
+            // skip the current cell if its parent sees the neighbour directly
+            let parent = match parents.get(&current.coord) {
+                Some(&parent) if maze.line_of_sight(parent, neighbour) => parent,
+                _ => current.coord,
+            };
+
+            let g_cost = g_costs[&parent] + distance(parent, neighbour);
+            if g_costs.get(&neighbour).is_none_or(|&old| g_cost < old) {
+                g_costs.insert(neighbour, g_cost);
+                parents.insert(neighbour, parent);
+                open_set.push(Node {
+                    coord: neighbour,
+                    g_cost,
+                    h_cost: distance(neighbour, end),
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// String pulling: drops every waypoint that the previous kept waypoint can see past, so a grid path becomes
+///  a few straight lines. The first and last waypoints are always kept.
+pub fn smooth_path(maze: &Maze, path: &[Coord]) -> Vec<Coord> {
+    let (&first, &last) = match (path.first(), path.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Vec::new(),
+    };
+
+    let mut smoothed = vec![first];
+    let mut anchor = first;
+    for window in path.windows(2) {
+        let (previous, next) = (window[0], window[1]);
+        if !maze.line_of_sight(anchor, next) {
+            smoothed.push(previous);
+            anchor = previous;
+        }
+    }
+
+    if anchor != last {
+        smoothed.push(last);
+    }
+    smoothed
+}
+
+/// total length of the straight lines between the waypoints
+pub fn path_length(path: &[Coord]) -> f32 {
+    path.windows(2).map(|w| distance(w[0], w[1])).sum()
+}
+
+#[cfg(test)]
+fn test_maze() -> Maze {
+    use crate::util::Array2D;
+
+    Maze {
+        grid: Array2D::from(
+            "\
+            ##########\n\
+            #........#\n\
+            #........#\n\
+            #...##...#\n\
+            #...##...#\n\
+            #........#\n\
+            ##########\n"
+                .to_string(),
+        ),
+    }
+}
+
+#[test]
+fn test_theta_star_goes_straight_through_open_areas() {
+    use super::a_star;
+
+    let maze = test_maze();
+
+    // nothing in the way, a single straight line
+    assert_eq!(
+        theta_star(&maze, (1, 1), (8, 2)),
+        Some(vec![(1, 1), (8, 2)])
+    );
+
+    // around the pillar, shorter than the grid path
+    let (start, end) = ((1, 4), (8, 3));
+    let path = theta_star(&maze, start, end).unwrap();
+    let grid_path = a_star(&maze, start, end).unwrap();
+    assert!(path.len() < grid_path.len());
+    assert!(path_length(&path) < (grid_path.len() - 1) as f32);
+    assert!(path.windows(2).all(|w| maze.line_of_sight(w[0], w[1])));
+}
+
+#[test]
+fn test_smooth_path() {
+    use super::a_star;
+
+    let maze = test_maze();
+
+    let grid_path = a_star(&maze, (1, 4), (8, 3)).unwrap();
+    let smoothed = smooth_path(&maze, &grid_path);
+
+    assert_eq!(smoothed.first(), grid_path.first());
+    assert_eq!(smoothed.last(), grid_path.last());
+    assert!(smoothed.len() < grid_path.len());
+    assert!(smoothed.windows(2).all(|w| maze.line_of_sight(w[0], w[1])));
+
+    assert_eq!(smooth_path(&maze, &[(2, 2)]), vec![(2, 2)]);
+    assert!(smooth_path(&maze, &[]).is_empty());
+}